@@ -1,24 +1,60 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use reqwest::ClientBuilder;
-use tracing::{debug, warn, error};
+use clap::ValueEnum;
+use reqwest::{Client, ClientBuilder, Proxy};
+use tracing::{info, warn};
 
 use crate::{
-    check_robots::Robot,
+    check_robots::{RobotsFetchResult, fetch_robots, fetch_robots_from},
     commands::{Cli, Commands},
-    extract_links::ExtractLinks,
+    doctor::run_checks,
+    extract_links::{ExtractLinks, ExtractLinksOptions},
     extract_metadata::PageMetadata,
-    fetch::fetch_page,
+    fetch::{FetchOptions, RateLimiter, RetryBudget, fetch_page},
     printer::pretty_printer,
+    profile::{ProfileOptions, SiteProfile, shutdown_on_ctrl_c},
+    seo_report::SeoReport,
+    sitemap::{discover_sitemaps, fetch_sitemap, validate_sitemap},
 };
 
-pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
-
-    let client = ClientBuilder::new()
+/// Builds the HTTP client shared by every request of an invocation
+/// Compressed responses are decoded transparently, so parsers always see plain bodies
+fn build_client(cli: &Cli) -> anyhow::Result<Client> {
+    let mut client_builder = ClientBuilder::new()
         .user_agent(cli.user_agent.clone())
         .timeout(Duration::from_secs(cli.timeout as u64))
-        .danger_accept_invalid_certs(false)
-        .build()?;
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        // fetch_page follows redirects itself, detecting loops and honoring max_redirects
+        .redirect(reqwest::redirect::Policy::none())
+        .danger_accept_invalid_certs(false);
+    if let Some(proxy) = &cli.proxy {
+        client_builder = client_builder.proxy(Proxy::all(proxy.as_str())?);
+    }
+    Ok(client_builder.build()?)
+}
+
+pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
+
+    let client = build_client(&cli)?;
+
+    let retry_budget = RetryBudget::new(cli.max_retries_total);
+    let seed = cli.seed.unwrap_or_else(|| fastrand::u64(..));
+    info!(seed, "Pass --seed {} to reproduce this run", seed);
+    let fetch_options = FetchOptions {
+        retry_delay: Duration::from_millis(cli.backoff_base),
+        backoff: cli.backoff,
+        jitter: cli.backoff_jitter,
+        retry_empty_body: cli.retry_empty_body,
+        rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(seed))),
+        host_user_agents: cli.host_user_agent.iter().cloned().collect(),
+        content_types: cli.content_types.clone(),
+        rate_limiter: cli.max_requests_per_second.map(|rate| Arc::new(RateLimiter::per_second(rate))),
+        ..Default::default()
+    };
 
     match cli.command {
         Commands::Fetch {
@@ -26,7 +62,7 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
             output_format,
         } => {
 
-            let page = fetch_page(&client, &url, 5, 3, Duration::from_secs(1)).await?;
+            let page = fetch_page(&client, &url, &fetch_options, &retry_budget).await?;
 
             match output_format {
                 crate::commands::OutputFormat::Json => {
@@ -35,7 +71,9 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                         "final_url": page.final_url.to_string(),
                         "status_code": page.status_code,
                         "content_type": page.content_type,
+                        "headers": page.headers,
                         "content_length": page.html_content.len(),
+                        "empty_body": page.empty_body,
                         "html":page.html_content,
                         "fetched_duration_ms": page.fetched_duration_ms,
                         "timestamp": page.timestamp.to_rfc3339(),
@@ -50,6 +88,7 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                             "Status Code": page.status_code,
                             "Content-Type": page.content_type.as_deref().unwrap_or("unknown"),
                             "Content Size": format!("{} bytes", page.html_content.len()),
+                            "Empty Body": page.empty_body,
                             "Fetch Duration": format!("{} ms", page.fetched_duration_ms),
                             "Timestamp": page.timestamp.to_rfc3339(),
                         }
@@ -63,17 +102,21 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
             rate_limit: _,
             internal_only,
             external_only,
+            only,
+            capture_data_attrs,
             output_format,
         } => {
             
-            let page = fetch_page(&client, &url, 5, 3, Duration::from_secs(1)).await?;
+            let page = fetch_page(&client, &url, &fetch_options, &retry_budget).await?;
 
             if let Some(document) = page.parsed_html {
-                let links = ExtractLinks::extract(&page.final_url, &document)?;
+                let options = ExtractLinksOptions { capture_data_attrs };
+                let mut links = ExtractLinks::extract_with(&page.final_url, &document, &options)?;
+                links.retain(&only);
 
                 match output_format {
                     crate::commands::OutputFormat::Json => {
-                        let json_output = if internal_only {
+                        let mut json_output = if internal_only {
                             serde_json::json!({
                                 "url": page.final_url.to_string(),
                                 "type": "internal_links",
@@ -113,9 +156,33 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 "javascript": {
                                     "count": links.javascript.len(),
                                     "links": links.javascript
+                                },
+                                "data": {
+                                    "count": links.data.len(),
+                                    "links": links.data
+                                },
+                                "frames": {
+                                    "count": links.frames.len(),
+                                    "links": links.frames
+                                },
+                                "images": {
+                                    "count": links.images.len(),
+                                    "images": links.images
                                 }
                             })
                         };
+                        if let Some(categories) = json_output.as_object_mut()
+                            && !only.is_empty()
+                        {
+                            categories.retain(|key, _| {
+                                key == "url"
+                                    || only.iter().any(|category| {
+                                        category
+                                            .to_possible_value()
+                                            .is_some_and(|value| value.get_name() == key)
+                                    })
+                            });
+                        }
                         println!("{}", serde_json::to_string_pretty(&json_output)?);
                     }
                     crate::commands::OutputFormat::Text => {
@@ -137,6 +204,9 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 if let Some(target) = &link.target {
                                     link_obj["Target"] = serde_json::json!(target);
                                 }
+                                if !link.data_attrs.is_empty() {
+                                    link_obj["Data Attributes"] = serde_json::json!(link.data_attrs);
+                                }
                                 links_array.push(link_obj);
                             }
                             let json_output = serde_json::json!({
@@ -165,6 +235,9 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 if let Some(target) = &link.target {
                                     link_obj["Target"] = serde_json::json!(target);
                                 }
+                                if !link.data_attrs.is_empty() {
+                                    link_obj["Data Attributes"] = serde_json::json!(link.data_attrs);
+                                }
                                 links_array.push(link_obj);
                             }
                             let json_output = serde_json::json!({
@@ -264,6 +337,53 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 });
                             }
 
+                            if !links.data.is_empty() {
+                                let mut data_array = Vec::new();
+                                for link in &links.data {
+                                    data_array.push(serde_json::json!({
+                                        "URL": link.url.to_string(),
+                                        "Text": link.text,
+                                    }));
+                                }
+                                all_links["Data URIs"] = serde_json::json!({
+                                    "Count": links.data.len(),
+                                    "Links": data_array
+                                });
+                            }
+
+                            if !links.frames.is_empty() {
+                                let mut frames_array = Vec::new();
+                                for link in &links.frames {
+                                    frames_array.push(serde_json::json!({
+                                        "URL": link.url.to_string(),
+                                    }));
+                                }
+                                all_links["Frame Links"] = serde_json::json!({
+                                    "Count": links.frames.len(),
+                                    "Links": frames_array
+                                });
+                            }
+
+                            if !links.images.is_empty() {
+                                let mut images_array = Vec::new();
+                                for image in &links.images {
+                                    let mut image_obj = serde_json::json!({
+                                        "URL": image.url,
+                                    });
+                                    if let Some(alt) = &image.alt {
+                                        image_obj["Alt"] = serde_json::json!(alt);
+                                    }
+                                    if let Some(loading) = &image.loading {
+                                        image_obj["Loading"] = serde_json::json!(loading);
+                                    }
+                                    images_array.push(image_obj);
+                                }
+                                all_links["Images"] = serde_json::json!({
+                                    "Count": links.images.len(),
+                                    "Images": images_array
+                                });
+                            }
+
                             let json_output = serde_json::json!({
                                 "All Links": all_links
                             });
@@ -282,100 +402,35 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
             url,
             rate_limit: _,
             include,
+            verify_og_image,
             output_format,
         } => {
             
-            let page = fetch_page(&client, &url, 5, 3, Duration::from_secs(1)).await?;
+            let page = fetch_page(&client, &url, &fetch_options, &retry_budget).await?;
 
             if let Some(document) = page.parsed_html {
                 let metadata = PageMetadata::extract(&document)?;
 
+                let mut og_images = Vec::new();
+                for image in &metadata.open_graph.og_images {
+                    let mut image_obj = serde_json::to_value(image)?;
+                    if verify_og_image {
+                        image_obj["content_length"] = match image.verify(&client, fetch_options.rate_limiter.as_deref()).await {
+                            Ok(size) => serde_json::json!(size),
+                            Err(e) => {
+                                warn!("Failed to verify og:image {}: {}", image.url, e);
+                                serde_json::Value::Null
+                            }
+                        };
+                    }
+                    og_images.push(image_obj);
+                }
+
                 match output_format {
                     crate::commands::OutputFormat::Json => {
-                        let mut json_output = serde_json::json!({
-                            "url": page.final_url.to_string(),
-                            "basic": {
-                                "title": metadata.basic.title,
-                                "description": metadata.basic.description,
-                                "keywords": metadata.basic.keywords,
-                                "charset": metadata.basic.charset,
-                                "language": metadata.basic.language,
-                            },
-                            "seo": {
-                                "robots": metadata.seo.robots,
-                                "canonical": metadata.seo.canonical.map(|u| u.to_string()),
-                                "author": metadata.seo.author,
-                                "publisher": metadata.seo.publisher,
-                                "creator": metadata.seo.creator,
-                            },
-                            "open_graph": {
-                                "og_type": metadata.open_graph.og_type,
-                                "og_title": metadata.open_graph.og_title,
-                                "og_description": metadata.open_graph.og_description,
-                                "og_url": metadata.open_graph.og_url.map(|u| u.to_string()),
-                                "og_image": metadata.open_graph.og_image.map(|u| u.to_string()),
-                                "og_site_name": metadata.open_graph.og_site_name,
-                                "og_locale": metadata.open_graph.og_locale,
-                            },
-                            "twitter_card": {
-                                "twitter_card": metadata.twitter_card.twitter_card,
-                                "twitter_title": metadata.twitter_card.twitter_title,
-                                "twitter_description": metadata.twitter_card.twitter_description,
-                                "twitter_url": metadata.twitter_card.twitter_url.map(|u| u.to_string()),
-                                "twitter_image": metadata.twitter_card.twitter_image.map(|u| u.to_string()),
-                            },
-                            "viewport": {
-                                "viewport": metadata.viewport.viewport,
-                                "theme_color": metadata.viewport.theme_color,
-                                "apple_mobile_web_app_capable": metadata.viewport.apple_mobile_web_app_capable,
-                                "apple_mobile_web_app_status_bar_style": metadata.viewport.apple_mobile_web_app_status_bar_style,
-                            },
-                        });
-
-                        // Add optional fields if requested
-                        if include
-                            .iter()
-                            .any(|i| i.to_lowercase() == "hreflang" || i.to_lowercase() == "links")
-                        {
-                            let hreflang: std::collections::HashMap<String, String> = metadata
-                                .links
-                                .alternate_languages
-                                .iter()
-                                .map(|(k, v)| (k.clone(), v.to_string()))
-                                .collect();
-                            json_output["links"]["alternate_languages"] =
-                                serde_json::to_value(hreflang)?;
-                        }
-
-                        if include
-                            .iter()
-                            .any(|i| i.to_lowercase() == "canonical" || i.to_lowercase() == "links")
-                        {
-                            json_output["links"]["canonical"] = serde_json::to_value(
-                                metadata.links.canonical.map(|u| u.to_string()),
-                            )?;
-                        }
-
-                        if include
-                            .iter()
-                            .any(|i| i.to_lowercase() == "author" || i.to_lowercase() == "links")
-                        {
-                            json_output["links"]["prev"] =
-                                serde_json::to_value(metadata.links.prev.map(|u| u.to_string()))?;
-                            json_output["links"]["next"] =
-                                serde_json::to_value(metadata.links.next.map(|u| u.to_string()))?;
-                        }
-
-                        if include
-                            .iter()
-                            .any(|i| i.to_lowercase() == "publisher" || i.to_lowercase() == "links")
-                        {
-                            json_output["links"]["icon"] =
-                                serde_json::to_value(metadata.links.icon.map(|u| u.to_string()))?;
-                            json_output["links"]["apple_touch_icon"] = serde_json::to_value(
-                                metadata.links.apple_touch_icon.map(|u| u.to_string()),
-                            )?;
-                        }
+                        let mut json_output = serde_json::to_value(&metadata)?;
+                        json_output["url"] = serde_json::json!(page.final_url.to_string());
+                        json_output["open_graph"]["og_images"] = serde_json::json!(og_images);
 
                         println!("{}", serde_json::to_string_pretty(&json_output)?);
                     }
@@ -395,6 +450,7 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 "Author": metadata.seo.author,
                                 "Publisher": metadata.seo.publisher,
                                 "Creator": metadata.seo.creator,
+                                "Content Rating": metadata.seo.content_rating,
                             },
                         });
 
@@ -408,6 +464,7 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                 "Description": metadata.open_graph.og_description,
                                 "URL": metadata.open_graph.og_url.map(|u| u.to_string()),
                                 "Image": metadata.open_graph.og_image.map(|u| u.to_string()),
+                                "Images": og_images,
                                 "Site Name": metadata.open_graph.og_site_name,
                                 "Locale": metadata.open_graph.og_locale,
                             });
@@ -438,6 +495,18 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                             });
                         }
 
+                        if let Some(ratings) = &metadata.ratings {
+                            metadata_obj["Ratings"] = serde_json::json!({
+                                "Rating": match ratings.best_rating {
+                                    Some(best) => format!("{} / {}", ratings.rating_value, best),
+                                    None => ratings.rating_value.to_string(),
+                                },
+                                "Normalized (0-5)": format!("{:.2}", ratings.normalized_rating),
+                                "Review Count": ratings.review_count,
+                                "Source": ratings.source,
+                            });
+                        }
+
                         if include.contains(&"links".to_string())
                             || include.iter().any(|i| {
                                 i.to_lowercase() == "hreflang"
@@ -487,58 +556,49 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
         },
         Commands::CheckRobot { url, output_format } => {
 
-            let robots_url = url.join("robots.txt")?;
-            debug!("Fetching robots.txt from: {}", robots_url);
-            
-            let response = match client.get(robots_url.clone()).send().await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    error!("Failed to fetch robots.txt: {}", e);
-                    return Err(anyhow::anyhow!("Failed to fetch robots.txt: {}", e));
+            let robots = match &cli.robots_url {
+                Some(robots_url) => {
+                    fetch_robots_from(&client, robots_url.as_str(), fetch_options.rate_limiter.as_deref()).await?
                 }
+                None => fetch_robots(&client, &url, fetch_options.rate_limiter.as_deref()).await?,
             };
+            // Match robots.txt with the same User-Agent the host's pages would be fetched with
+            let user_agent = fetch_options
+                .user_agent_for(&url)
+                .unwrap_or(&cli.user_agent)
+                .to_string();
 
-            let status = response.status();
-            debug!("robots.txt response status: {}", status);
-
-            // Handle different HTTP status codes per RFC 9309
-            let robot = match status.as_u16() {
-                404 => {
-                    warn!("robots.txt not found (404) - treating as all paths allowed");
-                    None
-                }
-                403 => {
-                    warn!("robots.txt forbidden (403) - treating as all paths disallowed (conservative)");
-                    // Return a special marker - we'll handle this in output
-                    Some("FORBIDDEN".to_string())
-                }
-                200 => {
-                    match response.text().await {
-                        Ok(text) => {
-                            debug!("Successfully fetched robots.txt (size: {} bytes)", text.len());
-                            Some(text)
-                        }
-                        Err(e) => {
-                            error!("Failed to read robots.txt response body: {}", e);
-                            return Err(anyhow::anyhow!("Failed to read robots.txt: {}", e));
-                        }
-                    }
-                }
-                code => {
-                    warn!("Unexpected robots.txt status code: {} - treating as all paths allowed", code);
-                    None
-                }
-            };
+            // A 403 or 5xx disallows everything; a missing robots.txt allows everything
+            let mut allowed = !matches!(robots, RobotsFetchResult::Forbidden | RobotsFetchResult::Unreachable(_));
 
             match output_format {
                 crate::commands::OutputFormat::Json => {
-                    match robot {
-                        Some(robot_text) if robot_text == "FORBIDDEN" => {
+                    match robots {
+                        RobotsFetchResult::Forbidden => {
                             let json_output = serde_json::json!({
                                 "url": url.to_string(),
-                                "user_agent": cli.user_agent,
+                                "user_agent": user_agent,
                                 "status": "forbidden",
                                 "message": "robots.txt returned 403 Forbidden - treating all paths as disallowed",
+                                "path": url.path(),
+                                "allowed": false,
+                                "matched_rule": "robots.txt returned 403 Forbidden",
+                                "crawl_delay": serde_json::Value::Null,
+                                "request_rate": serde_json::Value::Null,
+                                "sitemaps": Vec::<String>::new(),
+                                "groups": Vec::<serde_json::Value>::new(),
+                            });
+                            println!("{}", serde_json::to_string_pretty(&json_output)?);
+                        }
+                        RobotsFetchResult::Unreachable(status) => {
+                            let json_output = serde_json::json!({
+                                "url": url.to_string(),
+                                "user_agent": user_agent,
+                                "status": "unreachable",
+                                "message": format!("robots.txt returned {} - treating all paths as disallowed", status),
+                                "path": url.path(),
+                                "allowed": false,
+                                "matched_rule": format!("robots.txt returned {}", status),
                                 "crawl_delay": serde_json::Value::Null,
                                 "request_rate": serde_json::Value::Null,
                                 "sitemaps": Vec::<String>::new(),
@@ -546,30 +606,37 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                             });
                             println!("{}", serde_json::to_string_pretty(&json_output)?);
                         }
-                        Some(robot_text) => {
-                            let robot = Robot::new(robot_text);
-                            let group_info = robot.get_group_info(&cli.user_agent);
+                        RobotsFetchResult::Success(robot) => {
+                            let group_info = robot.get_group_info(&user_agent);
+                            let decision = robot.check(url.as_str(), &user_agent);
+                            allowed = decision.allowed;
                             
                             let json_output = serde_json::json!({
                                 "url": url.to_string(),
-                                "user_agent": cli.user_agent,
+                                "user_agent": user_agent,
                                 "status": "ok",
+                                "path": url.path(),
+                                "allowed": decision.allowed,
+                                "matched_rule": decision.reason,
                                 "matched_group": group_info.as_ref().map(|g| &g.user_agents),
                                 "rule_count": group_info.as_ref().map(|g| g.rule_count).unwrap_or(0),
                                 "allow_rules": group_info.as_ref().map(|g| g.allow_count).unwrap_or(0),
                                 "disallow_rules": group_info.as_ref().map(|g| g.disallow_count).unwrap_or(0),
-                                "crawl_delay": robot.crawl_delay(&cli.user_agent),
-                                "request_rate": robot.request_rate(&cli.user_agent),
+                                "crawl_delay": robot.crawl_delay(&user_agent),
+                                "request_rate": robot.request_rate(&user_agent),
                                 "sitemaps": robot.sitemaps(),
                             });
                             println!("{}", serde_json::to_string_pretty(&json_output)?);
                         }
-                        None => {
+                        RobotsFetchResult::NotFound => {
                             let json_output = serde_json::json!({
                                 "url": url.to_string(),
-                                "user_agent": cli.user_agent,
+                                "user_agent": user_agent,
                                 "status": "not_found",
                                 "message": "robots.txt not found (404) - treating as all paths allowed",
+                                "path": url.path(),
+                                "allowed": true,
+                                "matched_rule": serde_json::Value::Null,
                                 "crawl_delay": serde_json::Value::Null,
                                 "request_rate": serde_json::Value::Null,
                                 "sitemaps": Vec::<String>::new(),
@@ -580,27 +647,47 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                     }
                 }
                 crate::commands::OutputFormat::Text => {
-                    match robot {
-                        Some(robot_text) if robot_text == "FORBIDDEN" => {
+                    match robots {
+                        RobotsFetchResult::Forbidden => {
                             let json_output = serde_json::json!({
                                 "Robots.txt Check": {
                                     "URL": url.to_string(),
-                                    "User-Agent": cli.user_agent,
+                                    "User-Agent": user_agent,
                                     "Status": "⚠️  FORBIDDEN (403)",
+                                    "Path": url.path(),
+                                    "Allowed": "✗ DISALLOWED",
                                     "Behavior": "All paths are DISALLOWED (conservative)",
                                     "Reason": "robots.txt returned 403 Forbidden",
                                 }
                             });
                             println!("{}", pretty_printer(json_output)?);
                         }
-                        Some(robot_text) => {
-                            let robot = Robot::new(robot_text);
-                            let group_info = robot.get_group_info(&cli.user_agent);
+                        RobotsFetchResult::Unreachable(status) => {
+                            let json_output = serde_json::json!({
+                                "Robots.txt Check": {
+                                    "URL": url.to_string(),
+                                    "User-Agent": user_agent,
+                                    "Status": format!("⚠️  UNREACHABLE ({})", status),
+                                    "Path": url.path(),
+                                    "Allowed": "✗ DISALLOWED",
+                                    "Behavior": "All paths are DISALLOWED (RFC 9309)",
+                                    "Reason": format!("robots.txt returned {}", status),
+                                }
+                            });
+                            println!("{}", pretty_printer(json_output)?);
+                        }
+                        RobotsFetchResult::Success(robot) => {
+                            let group_info = robot.get_group_info(&user_agent);
+                            let decision = robot.check(url.as_str(), &user_agent);
+                            allowed = decision.allowed;
                             
                             let mut check_obj = serde_json::json!({
                                 "URL": url.to_string(),
-                                "User-Agent": cli.user_agent,
+                                "User-Agent": user_agent,
                                 "Status": "✓ OK",
+                                "Path": url.path(),
+                                "Allowed": if decision.allowed { "✓ ALLOWED" } else { "✗ DISALLOWED" },
+                                "Matched Rule": decision.reason,
                             });
 
                             if let Some(info) = group_info {
@@ -614,7 +701,7 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                                     check_obj["Crawl-Delay"] = serde_json::json!(format!("{} seconds", delay));
                                 }
                                 if let Some(rate) = info.request_rate {
-                                    check_obj["Request-Rate"] = serde_json::json!(format!("{} requests/second", rate));
+                                    check_obj["Request-Rate"] = serde_json::json!(format!("1 request every {} seconds", rate));
                                 }
                             } else {
                                 check_obj["Matched Group"] = serde_json::json!("* (wildcard)");
@@ -631,12 +718,14 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                             });
                             println!("{}", pretty_printer(json_output)?);
                         }
-                        None => {
+                        RobotsFetchResult::NotFound => {
                             let json_output = serde_json::json!({
                                 "Robots.txt Check": {
                                     "URL": url.to_string(),
-                                    "User-Agent": cli.user_agent,
+                                    "User-Agent": user_agent,
                                     "Status": "ℹ️  NOT FOUND (404)",
+                                    "Path": url.path(),
+                                    "Allowed": "✓ ALLOWED",
                                     "Behavior": "All paths are ALLOWED",
                                     "Reason": "robots.txt not found, default is permissive",
                                     "Crawl-Delay": "(not specified)",
@@ -649,6 +738,333 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
                     }
                 }
             }
+
+            if !allowed {
+                return Err(anyhow::anyhow!(
+                    "{} is disallowed by robots.txt for user-agent '{}'",
+                    url,
+                    user_agent
+                ));
+            }
+        }
+        Commands::Sitemaps { url, output_format } => {
+
+            let discovery = discover_sitemaps(
+                &client,
+                &url,
+                cli.robots_url.as_ref(),
+                fetch_options.rate_limiter.as_deref(),
+            )
+            .await?;
+
+            if let Some((fallback_url, exists)) = &discovery.fallback {
+                warn!(
+                    "robots.txt not found - {} {}",
+                    fallback_url,
+                    if *exists { "exists" } else { "does not exist" }
+                );
+            }
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let json_output = serde_json::json!({
+                        "url": url.to_string(),
+                        "sitemaps": discovery.sitemaps,
+                        "fallback_url": discovery.fallback.as_ref().map(|(fallback_url, _)| fallback_url),
+                        "fallback_exists": discovery.fallback.as_ref().map(|(_, exists)| exists),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => {
+                    match &discovery.fallback {
+                        Some((fallback_url, true)) => println!("No robots.txt found; {} exists", fallback_url),
+                        Some((fallback_url, false)) => {
+                            println!("No robots.txt found and {} does not exist", fallback_url)
+                        }
+                        None => {}
+                    }
+                    for sitemap in &discovery.sitemaps {
+                        println!("{}", sitemap);
+                    }
+                }
+            }
+        }
+        Commands::ValidateSitemap { url, output_format } => {
+
+            let xml = fetch_sitemap(&client, &url, fetch_options.rate_limiter.as_deref()).await?;
+            let report = validate_sitemap(&url, &xml);
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let json_output = serde_json::json!({
+                        "url": url.to_string(),
+                        "valid": report.is_valid(),
+                        "entries": report.entries,
+                        "violations": report.violations,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => {
+                    let mut check_obj = serde_json::json!({
+                        "URL": url.to_string(),
+                        "Entries": report.entries,
+                    });
+
+                    if report.is_valid() {
+                        check_obj["Status"] = serde_json::json!("✓ VALID");
+                    } else {
+                        check_obj["Status"] = serde_json::json!(format!(
+                            "✗ {} violation(s)",
+                            report.violations.len()
+                        ));
+                        let violations: Vec<String> = report
+                            .violations
+                            .iter()
+                            .map(|v| match (v.entry, &v.loc) {
+                                (Some(entry), Some(loc)) => {
+                                    format!("Entry {} ({}): {}", entry, loc, v.message)
+                                }
+                                (Some(entry), None) => format!("Entry {}: {}", entry, v.message),
+                                _ => v.message.clone(),
+                            })
+                            .collect();
+                        check_obj["Violations"] = serde_json::json!(violations);
+                    }
+
+                    let json_output = serde_json::json!({
+                        "Sitemap Validation": check_obj
+                    });
+                    println!("{}", pretty_printer(json_output)?);
+                }
+            }
+
+            if !report.is_valid() {
+                return Err(anyhow::anyhow!(
+                    "Sitemap has {} violation(s)",
+                    report.violations.len()
+                ));
+            }
+        }
+        Commands::SeoReport { url, output_format } => {
+
+            let page = fetch_page(&client, &url, &fetch_options, &retry_budget).await?;
+
+            let Some(document) = page.parsed_html else {
+                return Err(anyhow::anyhow!(
+                    "HTML parsing failed: unable to parse content from {}",
+                    page.final_url
+                ));
+            };
+            let report = SeoReport::audit(&page.final_url, &document)?;
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let json_output = serde_json::json!({
+                        "url": page.final_url.to_string(),
+                        "issue_count": report.issues.len(),
+                        "issues": report.issues,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => {
+                    let mut report_obj = serde_json::json!({
+                        "URL": page.final_url.to_string(),
+                    });
+
+                    if report.issues.is_empty() {
+                        report_obj["Status"] = serde_json::json!("✓ No issues found");
+                    } else {
+                        report_obj["Status"] = serde_json::json!(format!(
+                            "✗ {} issue(s)",
+                            report.issues.len()
+                        ));
+                        let issues: Vec<String> = report
+                            .issues
+                            .iter()
+                            .map(|issue| format!("[{}] {}", issue.code, issue.message))
+                            .collect();
+                        report_obj["Issues"] = serde_json::json!(issues);
+                    }
+
+                    let json_output = serde_json::json!({
+                        "SEO Report": report_obj
+                    });
+                    println!("{}", pretty_printer(json_output)?);
+                }
+            }
+        }
+        Commands::Doctor {
+            endpoint,
+            output_format,
+        } => {
+
+            let checks = run_checks(&client, &endpoint, cli.proxy.as_ref()).await;
+            let failed = checks.iter().filter(|check| !check.passed).count();
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let json_output = serde_json::json!({
+                        "passed": failed == 0,
+                        "checks": checks,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => {
+                    let mut checks_obj = serde_json::json!({});
+                    for check in &checks {
+                        checks_obj[check.name] = serde_json::json!(format!(
+                            "{} {}",
+                            if check.passed { "✓" } else { "✗" },
+                            check.detail
+                        ));
+                    }
+
+                    let json_output = serde_json::json!({
+                        "Doctor": checks_obj
+                    });
+                    println!("{}", pretty_printer(json_output)?);
+                }
+            }
+
+            if failed > 0 {
+                return Err(anyhow::anyhow!("{} doctor check(s) failed", failed));
+            }
+        }
+        Commands::Identify { url, output_format } => {
+
+            let homepage = url.join("/")?;
+            let page = fetch_page(&client, &homepage, &fetch_options, &retry_budget).await?;
+            let document = page.parsed_html.ok_or_else(|| {
+                anyhow::anyhow!("HTML parsing failed: unable to parse content from {}", page.final_url)
+            })?;
+            let identity = PageMetadata::extract(&document)?.site_identity;
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let json_output = serde_json::json!({
+                        "url": page.final_url.to_string(),
+                        "site_identity": identity,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => match identity {
+                    Some(identity) => {
+                        let json_output = serde_json::json!({
+                            "Site Identity": {
+                                "URL": page.final_url.to_string(),
+                                "Name": identity.name,
+                                "Site URL": identity.url,
+                                "Logo": identity.logo,
+                                "Same As": identity.same_as,
+                                "Search URL Template": identity.search_url_template,
+                            }
+                        });
+                        println!("{}", pretty_printer(json_output)?);
+                    }
+                    None => println!(
+                        "No Organization or WebSite JSON-LD found on {}",
+                        page.final_url
+                    ),
+                },
+            }
+        }
+        Commands::Profile {
+            url,
+            max_samples,
+            follow_iframes,
+            use_sitemap,
+            max_depth,
+            skip_adult,
+            limit_per_status,
+            deny_query_regex,
+            fail_on_robots_disallow_seed,
+            include,
+            exclude,
+            allow_domain,
+            block_domain,
+            strip_trailing_slash,
+            link_sample_rate,
+            output_format,
+        } => {
+
+            let user_agent = fetch_options.user_agent_for(&url).unwrap_or(&cli.user_agent);
+            let (decision, seed_robots) =
+                SiteProfile::check_seed_robots(&client, &url, user_agent, &fetch_options, cli.robots_url.as_ref()).await;
+            if !decision.allowed && fail_on_robots_disallow_seed {
+                return Err(anyhow::anyhow!(
+                    "Start URL {} is disallowed by robots.txt: {}",
+                    url,
+                    decision.reason
+                ));
+            }
+            let mut robots = SiteProfile::fetch_host_robots(&client, &url, &allow_domain, &fetch_options).await;
+            if let Some(host) = url.host_str()
+                && let Some(seed_robots) = seed_robots
+            {
+                robots.insert(host.to_lowercase(), seed_robots);
+            }
+            // Crawl-delay, or the interval implied by Request-rate, from the group matching our User-Agent
+            let mut crawl_delays = HashMap::new();
+            for (host, robots) in &robots {
+                if let RobotsFetchResult::Success(robot) = robots {
+                    let host_user_agent = fetch_options.host_user_agents.get(host).unwrap_or(&cli.user_agent);
+                    if let Some(delay) = robot.min_request_interval(host_user_agent) {
+                        info!(host, delay_ms = delay.as_millis(), "Honoring robots.txt crawl-delay");
+                        crawl_delays.insert(host.clone(), delay);
+                    }
+                }
+            }
+
+            let options = ProfileOptions {
+                max_samples,
+                follow_iframes,
+                use_sitemap,
+                max_depth,
+                skip_adult,
+                limit_per_status,
+                deny_query: deny_query_regex,
+                include,
+                exclude,
+                allow_domains: allow_domain,
+                block_domains: block_domain,
+                strip_trailing_slash,
+                link_sample_rate,
+                robots_url: cli.robots_url.clone(),
+                fetch: fetch_options,
+                crawl_delays,
+                robots,
+                user_agent: cli.user_agent.clone(),
+                shutdown: Arc::default(),
+            };
+            let listener = tokio::spawn(shutdown_on_ctrl_c(options.shutdown.clone()));
+            let profile = SiteProfile::sample(&client, &url, &options, &retry_budget).await;
+            listener.abort();
+            let profile = profile?;
+
+            match output_format {
+                crate::commands::OutputFormat::Json => {
+                    let mut json_output = serde_json::to_value(&profile)?;
+                    json_output["url"] = serde_json::json!(url.to_string());
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                }
+                crate::commands::OutputFormat::Text => {
+                    let json_output = serde_json::json!({
+                        "Site Profile": {
+                            "URL": url.to_string(),
+                            "Sampled URLs": profile.sampled,
+                            "Failed": profile.failed,
+                            "Content Types": profile.content_types,
+                            "Status Codes": profile.status_codes,
+                            "URLs by Status": profile.urls_by_status,
+                            "Average Page Size": format!("{} bytes", profile.average_page_size),
+                            "Canonical URLs": profile.canonical_urls,
+                            "Interrupted": profile.interrupted,
+                            "Disallowed by robots.txt": profile.robots_disallowed,
+                        }
+                    });
+                    println!("{}", pretty_printer(json_output)?);
+                }
+            }
         }
     }
 
@@ -659,9 +1075,15 @@ pub async fn execute_commands(cli: Cli) -> anyhow::Result<()> {
 mod test {
     use clap::Parser;
 
-    use crate::cli::execute_commands;
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use crate::cli::{build_client, execute_commands};
     use crate::commands::Cli;
-    use crate::utils::PythonServer;
+    use crate::extract_metadata::PageMetadata;
+    use crate::fetch::{FetchOptions, RetryBudget, fetch_page};
+    use crate::utils::{MockResponse, MockServer, PythonServer};
 
     #[rstest::fixture]
     fn python_server() -> PythonServer {
@@ -687,4 +1109,158 @@ mod test {
 
         Ok(())
     }
+
+    // Served from a MockServer rather than as a test_cli case: those all share port 9001
+    #[tokio::test]
+    async fn test_extract_metadata_json_output() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(include_str!("../test-site/pages/metadata.html"))
+        })
+        .await?;
+        let url = server.url.join("/pages/metadata.html")?;
+
+        let cli = Cli::parse_from(["crawler", "extract-metadata", url.as_str(), "--output-format", "json"]);
+        execute_commands(cli).await
+    }
+
+    #[tokio::test]
+    async fn test_fetch_detects_redirect_loop() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/a" => MockResponse::new(302).header("Location", "/b"),
+            _ => MockResponse::new(301).header("Location", "/a"),
+        })
+        .await?;
+        let url = server.url.join("/a")?;
+
+        let cli = Cli::parse_from(["crawler", "fetch", url.as_str()]);
+        let error = execute_commands(cli).await.expect_err("a redirect loop should fail");
+
+        assert_eq!(error.to_string(), format!("Redirect loop detected: {}", url));
+        // Not retried as a network error
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/a", "/b"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_leaves_303_307_308_redirects_to_fetch_page() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/start" => MockResponse::new(308).header("Location", "/see-other"),
+            "/see-other" => MockResponse::new(303).header("Location", "/temporary"),
+            "/temporary" => MockResponse::new(307).header("Location", "/final"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html></html>"),
+        })
+        .await?;
+        let url = server.url.join("/start")?;
+        let client = build_client(&Cli::parse_from(["crawler", "fetch", url.as_str()]))?;
+
+        let page = fetch_page(&client, &url, &FetchOptions::default(), &RetryBudget::default()).await?;
+        assert_eq!(page.final_url, server.url.join("/final")?);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/start", "/see-other", "/temporary", "/final"]);
+
+        // max_redirects only holds when fetch_page sees every hop
+        let options = FetchOptions {
+            max_redirects: 2,
+            ..Default::default()
+        };
+        let error = fetch_page(&client, &url, &options, &RetryBudget::default())
+            .await
+            .expect_err("three redirects exceed the limit");
+        assert_eq!(error.to_string(), "Too many redirects");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_identify_fetches_homepage() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(include_str!("../test-site/pages/organization.html"))
+        })
+        .await?;
+        let url = server.url.join("/pages/metadata.html")?;
+
+        for output_format in ["json", "text"] {
+            let cli = Cli::parse_from(["crawler", "identify", url.as_str(), "--output-format", output_format]);
+            execute_commands(cli).await?;
+        }
+
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_decodes_gzip_bodies() -> anyhow::Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"<html><head><title>Compressed Page</title></head></html>")?;
+        let gzipped = encoder.finish()?;
+
+        let server = MockServer::start(move |_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .header("Content-Encoding", "gzip")
+                .body(gzipped.clone())
+        })
+        .await?;
+        let client = build_client(&Cli::parse_from(["crawler", "fetch", server.url.as_str()]))?;
+
+        let page = fetch_page(&client, &server.url, &FetchOptions::default(), &RetryBudget::default()).await?;
+        let metadata = PageMetadata::extract(page.parsed_html.as_ref().unwrap())?;
+
+        assert_eq!(metadata.basic.title.as_deref(), Some("Compressed Page"));
+        assert!(server.requests()[0].header("accept-encoding").unwrap().contains("gzip"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sitemaps_reports_fallback() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/sitemap.xml" => MockResponse::new(200).body("<urlset></urlset>"),
+            _ => MockResponse::new(404),
+        })
+        .await?;
+
+        for output_format in ["json", "text"] {
+            let cli = Cli::parse_from(["crawler", "sitemaps", server.url.as_str(), "--output-format", output_format]);
+            execute_commands(cli).await?;
+        }
+
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/robots.txt", "/sitemap.xml", "/robots.txt", "/sitemap.xml"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_fails_on_disallowed_seed() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body("User-agent: *\nDisallow: /private/\n"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html><body>Private</body></html>"),
+        })
+        .await?;
+        let seed = server.url.join("/private/start")?;
+
+        let cli = Cli::parse_from(["crawler", "profile", seed.as_str()]);
+        execute_commands(cli).await?;
+
+        let cli = Cli::parse_from(["crawler", "profile", seed.as_str(), "--fail-on-robots-disallow-seed"]);
+        let error = execute_commands(cli).await.expect_err("disallowed seed should fail");
+        assert!(error.to_string().contains("disallowed by robots.txt"), "{error}");
+
+        Ok(())
+    }
 }
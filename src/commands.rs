@@ -1,6 +1,10 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
 use url::Url;
 
+use crate::extract_links::LinkCategory;
+use crate::fetch::{Backoff, Jitter};
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum OutputFormat {
     Json,
@@ -28,6 +32,12 @@ pub enum Commands {
         /// Only return external links
         #[arg(long)]
         external_only: bool,
+        /// Comma-separated categories to report, e.g. internal,external (default: all)
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["internal_only", "external_only"])]
+        only: Vec<LinkCategory>,
+        /// Include each anchor's data-* attributes in the output
+        #[arg(long)]
+        capture_data_attrs: bool,
         /// Output format: json or default text (default: text)
         #[arg(long, value_parser, default_value = "text")]
         output_format: OutputFormat,
@@ -38,20 +48,119 @@ pub enum Commands {
         /// Max requests per minute per domain (default: 10, resets per CLI invocation)
         #[arg(long, default_value_t = 10)]
         rate_limit: i64,
-        /// Comma-separated optional fields (hreflang, canonical, author, publisher)
+        /// Comma-separated optional fields for text output (hreflang, canonical, author, publisher); JSON always has every field
         #[arg(long)]
         include: Vec<String>,
+        /// Send a HEAD request for each og:image and report its actual size
+        #[arg(long)]
+        verify_og_image: bool,
         /// Output format: json or default text (default: text)
         #[arg(long, value_parser, default_value = "text")]
         output_format: OutputFormat,
     },
+    /// Check whether a URL may be crawled according to its host's robots.txt
+    /// Exits non-zero when the URL is disallowed for the configured user-agent
+    #[command(visible_alias = "check-robots")]
     CheckRobot {
-        /// Target URL to fetch (required)
+        /// Target URL to check (required)
         url: Url,
         /// Output format: json or default text (default: text)
         #[arg(long, value_parser, default_value = "text")]
         output_format: OutputFormat,
-    }
+    },
+    /// List the sitemaps advertised in a host's robots.txt
+    /// Falls back to checking <host>/sitemap.xml when robots.txt is missing
+    Sitemaps {
+        /// Any URL on the target host (required)
+        url: Url,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
+    /// Validate a sitemap against the sitemaps.org protocol
+    ValidateSitemap {
+        /// Sitemap URL to validate (required)
+        url: Url,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
+    /// Audit a single page for common SEO problems
+    SeoReport {
+        /// Target URL to audit (required)
+        url: Url,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
+    /// Check that the crawler can resolve DNS, connect to the `--proxy` if set, and reach the network
+    /// Exits non-zero when any check fails
+    Doctor {
+        /// Endpoint to request for the connectivity check (default: https://example.com/)
+        #[arg(long, default_value = "https://example.com/")]
+        endpoint: Url,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
+    /// Report the site identity (name, logo, social profiles, search endpoint) declared on the homepage
+    Identify {
+        /// Any URL on the target site; its homepage is fetched (required)
+        url: Url,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
+    /// Sample a site breadth-first and report its content types, status codes and page sizes
+    Profile {
+        /// Target URL to start sampling from (required)
+        url: Url,
+        /// Maximum number of URLs to sample (default: 20)
+        #[arg(long, default_value_t = 20)]
+        max_samples: usize,
+        /// Also sample same-site <iframe>/<frame> sources
+        #[arg(long)]
+        follow_iframes: bool,
+        /// Seed the sample with the same-site URLs listed in the site's sitemaps
+        #[arg(long)]
+        use_sitemap: bool,
+        /// Maximum link depth from the start URL; 0 samples the start URL only (default: unlimited)
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// Do not follow links from pages that declare an adult content rating
+        #[arg(long)]
+        skip_adult: bool,
+        /// Exit with an error instead of a warning when robots.txt disallows the start URL
+        #[arg(long)]
+        fail_on_robots_disallow_seed: bool,
+        /// Only enqueue URLs matching this regex, repeatable (default: every internal URL)
+        #[arg(long)]
+        include: Vec<Regex>,
+        /// Never enqueue URLs matching this regex, repeatable; takes precedence over --include
+        #[arg(long)]
+        exclude: Vec<Regex>,
+        /// Also follow links to this host, repeatable (default: the start URL's host only)
+        #[arg(long)]
+        allow_domain: Vec<String>,
+        /// Never enqueue URLs on this host, repeatable; takes precedence over --allow-domain
+        #[arg(long)]
+        block_domain: Vec<String>,
+        /// Treat /a/ and /a as the same page when deduplicating
+        #[arg(long)]
+        strip_trailing_slash: bool,
+        /// Probability (0.0-1.0) of enqueueing each discovered link; the start URL and sitemap seeds are always kept (default: 1.0)
+        #[arg(long, default_value_t = 1.0, value_parser = parse_probability)]
+        link_sample_rate: f64,
+        /// Keep at most N example URLs per status code; every response is still counted (default: unlimited)
+        #[arg(long)]
+        limit_per_status: Option<usize>,
+        /// Never enqueue URLs whose query string matches this regex, repeatable
+        #[arg(long)]
+        deny_query_regex: Vec<Regex>,
+        /// Output format: json or default text (default: text)
+        #[arg(long, value_parser, default_value = "text")]
+        output_format: OutputFormat,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -59,10 +168,61 @@ pub enum Commands {
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
-    /// Custom User-Agent string (default: "Marahuyo-Crawler/0.1.0")
-    #[arg(long, default_value = "Marahuyo-Crawler/0.1.0")]
+    /// Custom User-Agent string (default: "Marahuyo-Crawler/<version>")
+    #[arg(long, default_value = concat!("Marahuyo-Crawler/", env!("CARGO_PKG_VERSION")))]
     pub user_agent: String,
     /// HTTP request timeout in seconds (default: 30)
     #[arg(long, default_value_t = 30)]
     pub timeout: i64,
+    /// Send every request through this proxy (http or https URL)
+    #[arg(long)]
+    pub proxy: Option<Url>,
+    /// Maximum number of requests per second across the whole invocation: pages, retries, robots.txt, sitemaps and og:image checks (default: unlimited)
+    #[arg(long, visible_alias = "rate-limit-global", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_requests_per_second: Option<u32>,
+    /// Maximum number of retries across all requests of this invocation (default: unlimited)
+    #[arg(long)]
+    pub max_retries_total: Option<usize>,
+    /// How the delay between retries grows: exponential, linear or constant (default: exponential)
+    #[arg(long, value_enum, default_value = "exponential")]
+    pub backoff: Backoff,
+    /// Base retry delay in milliseconds the backoff strategy scales from (default: 1000)
+    #[arg(long, default_value_t = 1000)]
+    pub backoff_base: u64,
+    /// Randomization applied to retry delays: full, equal or none (default: none)
+    #[arg(long, value_enum, default_value = "none")]
+    pub backoff_jitter: Jitter,
+    /// Retry 200 responses with an empty body, up to the usual retry limits
+    #[arg(long)]
+    pub retry_empty_body: bool,
+    /// Seed for every randomized decision (e.g. retry jitter); a random seed is chosen and logged when omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Comma-separated media types parsed as HTML; others are fetched but not parsed (e.g. text/*)
+    #[arg(long, value_delimiter = ',', default_value = "text/html,application/xhtml+xml")]
+    pub content_types: Vec<String>,
+    /// User-Agent for a specific host as HOST=UA, repeatable; other hosts use --user-agent
+    #[arg(long, value_parser = parse_host_user_agent)]
+    pub host_user_agent: Vec<(String, String)>,
+    /// Fetch robots.txt from this URL instead of <host>/robots.txt
+    #[arg(long)]
+    pub robots_url: Option<Url>,
+}
+
+/// Parses a probability between 0.0 and 1.0 inclusive
+fn parse_probability(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("expected a number between 0.0 and 1.0, got '{}'", value)),
+    }
+}
+
+/// Parses a `HOST=UA` pair, lowercasing the host so lookups are case-insensitive
+fn parse_host_user_agent(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((host, user_agent)) if !host.trim().is_empty() && !user_agent.trim().is_empty() => {
+            Ok((host.trim().to_lowercase(), user_agent.trim().to_string()))
+        }
+        _ => Err(format!("expected HOST=USER_AGENT, got '{}'", value)),
+    }
 }
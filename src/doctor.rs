@@ -0,0 +1,152 @@
+use reqwest::Client;
+use serde::Serialize;
+use tracing::debug;
+use url::Url;
+
+/// Outcome of a single environment check
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    /// What was verified, or why the check failed
+    pub detail: String,
+}
+
+/// Verifies the crawler can reach the network: DNS resolution, the proxy when one is set, and an
+/// outbound request to `endpoint`
+/// Every check runs even when an earlier one fails, so the report shows the full picture
+pub async fn run_checks(client: &Client, endpoint: &Url, proxy: Option<&Url>) -> Vec<DoctorCheck> {
+    let mut checks = vec![check_dns(endpoint).await];
+    if let Some(proxy) = proxy {
+        checks.push(check_proxy(proxy).await);
+    }
+    checks.push(check_request(client, endpoint).await);
+    checks
+}
+
+async fn check_dns(endpoint: &Url) -> DoctorCheck {
+    let name = "DNS resolution";
+    let (Some(host), Some(port)) = (endpoint.host_str(), endpoint.port_or_known_default()) else {
+        return DoctorCheck {
+            name,
+            passed: false,
+            detail: format!("{} has no host to resolve", endpoint),
+        };
+    };
+
+    debug!("Resolving {}", host);
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(addresses) => {
+            let addresses: Vec<String> = addresses.map(|a| a.ip().to_string()).collect();
+            DoctorCheck {
+                name,
+                passed: !addresses.is_empty(),
+                detail: format!("{} resolved to {}", host, addresses.join(", ")),
+            }
+        }
+        Err(e) => DoctorCheck {
+            name,
+            passed: false,
+            detail: format!("Failed to resolve {}: {}", host, e),
+        },
+    }
+}
+
+async fn check_proxy(proxy: &Url) -> DoctorCheck {
+    let name = "Proxy connection";
+    let (Some(host), Some(port)) = (proxy.host_str(), proxy.port_or_known_default()) else {
+        return DoctorCheck {
+            name,
+            passed: false,
+            detail: format!("{} has no host and port to connect to", proxy),
+        };
+    };
+
+    debug!("Connecting to proxy {}:{}", host, port);
+    match tokio::net::TcpStream::connect((host, port)).await {
+        Ok(_) => DoctorCheck {
+            name,
+            passed: true,
+            detail: format!("Connected to proxy {}:{}", host, port),
+        },
+        Err(e) => DoctorCheck {
+            name,
+            passed: false,
+            detail: format!("Failed to connect to proxy {}:{}: {}", host, port, e),
+        },
+    }
+}
+
+async fn check_request(client: &Client, endpoint: &Url) -> DoctorCheck {
+    let name = "Outbound request";
+
+    debug!("Requesting {}", endpoint);
+    match client.get(endpoint.clone()).send().await {
+        // Any HTTP response proves connectivity, whatever its status
+        Ok(response) => DoctorCheck {
+            name,
+            passed: true,
+            detail: format!("{} answered with {}", endpoint, response.status()),
+        },
+        Err(e) => DoctorCheck {
+            name,
+            passed: false,
+            detail: format!("Request to {} failed: {}", endpoint, e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::Client;
+    use url::Url;
+
+    use crate::doctor::run_checks;
+    use crate::utils::{MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_checks_pass_against_reachable_endpoint() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| MockResponse::new(503)).await?;
+
+        let checks = run_checks(&Client::new(), &server.url, None).await;
+
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|check| check.passed), "{checks:?}");
+        assert!(checks[1].detail.contains("503"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_checks_fail_for_unresolvable_host() -> anyhow::Result<()> {
+        let endpoint = Url::parse("http://crawler-doctor.invalid/")?;
+
+        let checks = run_checks(&Client::new(), &endpoint, None).await;
+
+        assert!(checks.iter().all(|check| !check.passed), "{checks:?}");
+        assert!(checks[0].detail.contains("crawler-doctor.invalid"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_proxy_is_checked_separately() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| MockResponse::new(200)).await?;
+        // Bind and drop a listener to find a port nothing listens on
+        let closed_port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let dead_proxy = Url::parse(&format!("http://127.0.0.1:{closed_port}"))?;
+
+        let checks = run_checks(&Client::new(), &server.url, Some(&server.url)).await;
+        let names: Vec<&str> = checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, ["DNS resolution", "Proxy connection", "Outbound request"]);
+        assert!(checks.iter().all(|check| check.passed), "{checks:?}");
+
+        let checks = run_checks(&Client::new(), &server.url, Some(&dead_proxy)).await;
+        assert!(!checks[1].passed, "{checks:?}");
+        assert!(checks[1].detail.contains(&closed_port.to_string()));
+        // The direct client still reaches the endpoint, so only the proxy check fails
+        assert!(checks[2].passed, "{checks:?}");
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
+use clap::ValueEnum;
 use scraper::Selector;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use tracing::{debug, warn};
 use url::Url;
 
@@ -11,6 +12,42 @@ pub struct LinkInfo {
     pub title: Option<String>,
     pub rel: Option<String>,
     pub target: Option<String>,
+    /// `data-*` attributes of the anchor keyed by full attribute name, when captured
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub data_attrs: BTreeMap<String, String>,
+}
+
+impl LinkInfo {
+    /// Returns true when the space-separated `rel` attribute contains `nofollow` (case-insensitive)
+    pub fn is_nofollow(&self) -> bool {
+        self.rel.as_deref().is_some_and(|rel| {
+            rel.split_ascii_whitespace()
+                .any(|value| value.eq_ignore_ascii_case("nofollow"))
+        })
+    }
+}
+
+/// An image referenced by an `<img>` element
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct ImageInfo {
+    /// `src`, or the first `srcset` candidate when `src` is missing, resolved to an absolute URL
+    pub url: String,
+    pub alt: Option<String>,
+    pub loading: Option<String>,
+}
+
+/// Categories of [`ExtractLinks`], named as in the JSON output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LinkCategory {
+    Internal,
+    External,
+    Mailto,
+    Phone,
+    Anchor,
+    Javascript,
+    Data,
+    Frames,
+    Images,
 }
 
 #[derive(Debug, Clone)]
@@ -27,22 +64,58 @@ pub struct ExtractLinks {
     pub anchor: Vec<LinkInfo>,
     /// javascript: protocol
     pub javascript: Vec<LinkInfo>,
+    /// data: URIs, kept verbatim
+    pub data: Vec<LinkInfo>,
+    /// `<iframe src>` and `<frame src>` sources, resolved to absolute URLs
+    pub frames: Vec<LinkInfo>,
+    /// `<img>` sources, resolved to absolute URLs
+    pub images: Vec<ImageInfo>,
+}
+
+/// Optional behaviour for [`ExtractLinks::extract_with`]
+#[derive(Debug, Clone, Default)]
+pub struct ExtractLinksOptions {
+    /// Collect the `data-*` attributes of each anchor into [`LinkInfo::data_attrs`]
+    pub capture_data_attrs: bool,
 }
 
 impl ExtractLinks {
     pub fn extract(url: &Url, document: &scraper::Html) -> anyhow::Result<Self> {
+        Self::extract_with(url, document, &ExtractLinksOptions::default())
+    }
+
+    pub fn extract_with(
+        url: &Url,
+        document: &scraper::Html,
+        options: &ExtractLinksOptions,
+    ) -> anyhow::Result<Self> {
         let mut internal = Vec::new();
         let mut external = Vec::new();
         let mut mailto = Vec::new();
         let mut phone = Vec::new();
         let mut anchor = Vec::new();
         let mut javascript = Vec::new();
+        let mut data = Vec::new();
+        let mut frames = Vec::new();
+        let mut images = Vec::new();
 
         let href_selector = Selector::parse("a[href]").unwrap();
         let source_domain = url.domain().unwrap_or("");
 
         debug!("Extracting links from: {}", url);
 
+        // Relative references resolve against the first <base href>, falling back to the page URL
+        let base_selector = Selector::parse("base[href]").unwrap();
+        let base = document
+            .select(&base_selector)
+            .next()
+            .and_then(|element| element.value().attr("href"))
+            .and_then(|href| url.join(href.trim()).ok())
+            .unwrap_or_else(|| url.clone());
+        if &base != url {
+            debug!("Resolving links against base: {}", base);
+        }
+
         for element in document.select(&href_selector) {
             if let Some(href) = element.value().attr("href") {
                 // Skip empty hrefs
@@ -62,6 +135,16 @@ impl ExtractLinks {
                 let title = element.value().attr("title").map(|s| s.to_string());
                 let rel = element.value().attr("rel").map(|s| s.to_string());
                 let target = element.value().attr("target").map(|s| s.to_string());
+                let data_attrs: BTreeMap<String, String> = if options.capture_data_attrs {
+                    element
+                        .value()
+                        .attrs()
+                        .filter(|(name, _)| name.starts_with("data-"))
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect()
+                } else {
+                    BTreeMap::new()
+                };
 
                 // Create LinkInfo helper
                 let create_link_info = |url_str: String| -> LinkInfo {
@@ -71,6 +154,7 @@ impl ExtractLinks {
                         title: title.clone(),
                         rel: rel.clone(),
                         target: target.clone(),
+                        data_attrs: data_attrs.clone(),
                     }
                 };
 
@@ -78,6 +162,10 @@ impl ExtractLinks {
                 if href.starts_with("javascript:") {
                     debug!("Found javascript link: {} (text: {})", href, text);
                     javascript.push(create_link_info(href.to_string()));
+                } else if href.starts_with("data:") {
+                    // Inline blobs are not resolvable and must never reach the crawl queue
+                    debug!("Found data URI link (text: {})", text);
+                    data.push(create_link_info(href.to_string()));
                 } else if href.starts_with("mailto:") {
                     debug!("Found mailto link: {} (text: {})", href, text);
                     mailto.push(create_link_info(href.to_string()));
@@ -87,7 +175,7 @@ impl ExtractLinks {
                 } else if href.starts_with("#") {
                     // Anchor/fragment link
                     debug!("Found anchor link: {} (text: {})", href, text);
-                    if let Ok(anchor_url) = url.join(href) {
+                    if let Ok(anchor_url) = base.join(href) {
                         anchor.push(create_link_info(anchor_url.to_string()));
                     }
                 } else {
@@ -95,8 +183,11 @@ impl ExtractLinks {
                     let parsed_url = if href.starts_with("http://") || href.starts_with("https://")
                     {
                         Url::parse(href)
+                    } else if href.starts_with("//") {
+                        // Protocol-relative: same scheme as the page, host taken from the href
+                        Url::parse(&format!("{}:{}", url.scheme(), href))
                     } else {
-                        url.join(href)
+                        base.join(href)
                     };
 
                     if let Ok(parsed) = parsed_url {
@@ -123,6 +214,73 @@ impl ExtractLinks {
             }
         }
 
+        let frame_selector = Selector::parse("iframe[src], frame[src]").unwrap();
+        for element in document.select(&frame_selector) {
+            let Some(src) = element.value().attr("src").map(str::trim) else {
+                continue;
+            };
+
+            // Blank, inline and scripted frames have no document worth crawling
+            if src.is_empty()
+                || src.starts_with("about:")
+                || src.starts_with("data:")
+                || src.starts_with("javascript:")
+            {
+                debug!("Skipping frame source: {}", src);
+                continue;
+            }
+
+            match base.join(src) {
+                Ok(parsed) => {
+                    debug!("Found frame: {}", parsed);
+                    frames.push(LinkInfo {
+                        url: parsed.to_string(),
+                        text: String::new(),
+                        title: element.value().attr("title").map(|s| s.to_string()),
+                        rel: None,
+                        target: element.value().attr("name").map(|s| s.to_string()),
+                        data_attrs: BTreeMap::new(),
+                    });
+                }
+                Err(_) => warn!("Failed to parse frame src: {}", src),
+            }
+        }
+
+        let image_selector = Selector::parse("img[src], img[srcset]").unwrap();
+        for element in document.select(&image_selector) {
+            let src = element
+                .value()
+                .attr("src")
+                .map(str::trim)
+                .filter(|src| !src.is_empty())
+                .or_else(|| {
+                    // Candidates are comma-separated "URL [descriptor]" pairs
+                    let srcset = element.value().attr("srcset")?;
+                    let candidate = srcset.split(',').next()?;
+                    candidate.split_ascii_whitespace().next()
+                });
+            let Some(src) = src else {
+                continue;
+            };
+
+            // Inline images are not assets on the server
+            if src.starts_with("data:") {
+                continue;
+            }
+
+            match base.join(src) {
+                Ok(parsed) => {
+                    debug!("Found image: {}", parsed);
+                    images.push(ImageInfo {
+                        url: parsed.to_string(),
+                        alt: element.value().attr("alt").map(|s| s.to_string()),
+                        loading: element.value().attr("loading").map(|s| s.to_string()),
+                    });
+                }
+                Err(_) => warn!("Failed to parse image src: {}", src),
+            }
+        }
+
         // Track counts before deduplication
         let internal_before = internal.len();
         let external_before = external.len();
@@ -130,38 +288,20 @@ impl ExtractLinks {
         let phone_before = phone.len();
         let anchor_before = anchor.len();
         let javascript_before = javascript.len();
+        let data_before = data.len();
+        let frames_before = frames.len();
+        let images_before = images.len();
 
-        // Deduplicate links using HashSet based on URL
-        internal = internal
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        external = external
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        mailto = mailto
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        phone = phone
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        anchor = anchor
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
-        javascript = javascript
-            .into_iter()
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect();
+        // Deduplicate links by URL, keeping document order
+        internal = Self::deduplicate(internal, |link| &link.url);
+        external = Self::deduplicate(external, |link| &link.url);
+        mailto = Self::deduplicate(mailto, |link| &link.url);
+        phone = Self::deduplicate(phone, |link| &link.url);
+        anchor = Self::deduplicate(anchor, |link| &link.url);
+        javascript = Self::deduplicate(javascript, |link| &link.url);
+        data = Self::deduplicate(data, |link| &link.url);
+        frames = Self::deduplicate(frames, |link| &link.url);
+        images = Self::deduplicate(images, |image| &image.url);
 
         // Track counts after deduplication
         let internal_after = internal.len();
@@ -170,6 +310,9 @@ impl ExtractLinks {
         let phone_after = phone.len();
         let anchor_after = anchor.len();
         let javascript_after = javascript.len();
+        let data_after = data.len();
+        let frames_after = frames.len();
+        let images_after = images.len();
 
         debug!(
             internal_count = %format!("{} -> {}", internal_before, internal_after),
@@ -178,7 +321,10 @@ impl ExtractLinks {
             phone_count = %format!("{} -> {}", phone_before, phone_after),
             anchor_count = %format!("{} -> {}", anchor_before, anchor_after),
             javascript_count = %format!("{} -> {}", javascript_before, javascript_after),
-            duplicates_removed = %format!("{} total", (internal_before - internal_after) + (external_before - external_after) + (mailto_before - mailto_after) + (phone_before - phone_after) + (anchor_before - anchor_after) + (javascript_before - javascript_after)),
+            data_count = %format!("{} -> {}", data_before, data_after),
+            frames_count = %format!("{} -> {}", frames_before, frames_after),
+            images_count = %format!("{} -> {}", images_before, images_after),
+            duplicates_removed = %format!("{} total", (internal_before - internal_after) + (external_before - external_after) + (mailto_before - mailto_after) + (phone_before - phone_after) + (anchor_before - anchor_after) + (javascript_before - javascript_after) + (data_before - data_after) + (frames_before - frames_after) + (images_before - images_after)),
             "Link extraction and deduplication complete"
         );
 
@@ -189,6 +335,313 @@ impl ExtractLinks {
             phone,
             anchor,
             javascript,
+            data,
+            frames,
+            images,
         })
     }
+
+    /// Empties every category not listed in `only`; an empty list keeps them all
+    pub fn retain(&mut self, only: &[LinkCategory]) {
+        if only.is_empty() {
+            return;
+        }
+        let keep = |category| only.contains(&category);
+        if !keep(LinkCategory::Internal) {
+            self.internal.clear();
+        }
+        if !keep(LinkCategory::External) {
+            self.external.clear();
+        }
+        if !keep(LinkCategory::Mailto) {
+            self.mailto.clear();
+        }
+        if !keep(LinkCategory::Phone) {
+            self.phone.clear();
+        }
+        if !keep(LinkCategory::Anchor) {
+            self.anchor.clear();
+        }
+        if !keep(LinkCategory::Javascript) {
+            self.javascript.clear();
+        }
+        if !keep(LinkCategory::Data) {
+            self.data.clear();
+        }
+        if !keep(LinkCategory::Frames) {
+            self.frames.clear();
+        }
+        if !keep(LinkCategory::Images) {
+            self.images.clear();
+        }
+    }
+
+    /// Keeps the first occurrence of each URL, preserving order
+    fn deduplicate<T>(items: Vec<T>, url: impl Fn(&T) -> &str) -> Vec<T> {
+        let mut seen = HashSet::new();
+        items
+            .into_iter()
+            .filter(|item| seen.insert(url(item).to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use url::Url;
+
+    use crate::extract_links::{ExtractLinks, ExtractLinksOptions, ImageInfo, LinkCategory, LinkInfo};
+
+    #[test]
+    fn test_is_nofollow() {
+        let link = |rel: Option<&str>| LinkInfo {
+            url: "https://example.com/".to_string(),
+            text: String::new(),
+            title: None,
+            rel: rel.map(|r| r.to_string()),
+            target: None,
+            data_attrs: BTreeMap::new(),
+        };
+
+        assert!(link(Some("nofollow")).is_nofollow());
+        assert!(link(Some("nofollow noopener")).is_nofollow());
+        assert!(link(Some("noopener  NoFollow")).is_nofollow());
+        assert!(!link(Some("noopener noreferrer")).is_nofollow());
+        assert!(!link(Some("nofollowing")).is_nofollow());
+        assert!(!link(None).is_nofollow());
+    }
+
+    #[test]
+    fn test_data_attributes_are_captured_when_enabled() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <a href="/pricing" data-category="nav" data-testid="pricing-link" data-track-event="click" class="x">Pricing</a>
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+        assert!(links.internal[0].data_attrs.is_empty());
+
+        let options = ExtractLinksOptions {
+            capture_data_attrs: true,
+        };
+        let links = ExtractLinks::extract_with(&url, &document, &options)?;
+
+        assert_eq!(
+            links.internal[0].data_attrs,
+            BTreeMap::from([
+                ("data-category".to_string(), "nav".to_string()),
+                ("data-testid".to_string(), "pricing-link".to_string()),
+                ("data-track-event".to_string(), "click".to_string()),
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retain_categories() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/")?;
+        let document = scraper::Html::parse_document(
+            r##"<html><body>
+                <a href="/about">About</a>
+                <a href="https://other.example.org/">Other</a>
+                <a href="mailto:hi@example.com">Mail</a>
+                <a href="#top">Top</a>
+            </body></html>"##,
+        );
+
+        let mut links = ExtractLinks::extract(&url, &document)?;
+        links.retain(&[]);
+        assert_eq!(links.mailto.len(), 1);
+
+        links.retain(&[LinkCategory::Internal, LinkCategory::External]);
+        assert_eq!(links.internal.len(), 1);
+        assert_eq!(links.external.len(), 1);
+        assert!(links.mailto.is_empty());
+        assert!(links.anchor.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduplication_preserves_document_order() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <a href="/c">C</a>
+                <a href="/a">A</a>
+                <a href="/c">C again</a>
+                <a href="/b">B</a>
+                <a href="/a">A again</a>
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+        let internal: Vec<(&str, &str)> = links
+            .internal
+            .iter()
+            .map(|l| (l.url.as_str(), l.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            internal,
+            [
+                ("https://example.com/c", "C"),
+                ("https://example.com/a", "A"),
+                ("https://example.com/b", "B"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_uris_are_kept_apart() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/pages/index.html")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <a href="data:text/plain;base64,SGVsbG8=">Download</a>
+                <a href="/about">About</a>
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+
+        assert_eq!(links.data.len(), 1);
+        assert_eq!(links.data[0].url, "data:text/plain;base64,SGVsbG8=");
+        assert_eq!(links.internal.len(), 1);
+        assert!(links.external.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_protocol_relative_links() -> anyhow::Result<()> {
+        let url = Url::parse("http://example.com/pages/index.html")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <a href="//example.com/about">Same domain</a>
+                <a href="//cdn.example.org/x.js">Other domain</a>
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+
+        assert_eq!(links.internal.len(), 1);
+        assert_eq!(links.internal[0].url, "http://example.com/about");
+        assert_eq!(links.external.len(), 1);
+        assert_eq!(links.external[0].url, "http://cdn.example.org/x.js");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_base_href_is_used_for_resolution() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/pages/base-href.html")?;
+        let document =
+            scraper::Html::parse_document(include_str!("../test-site/pages/base-href.html"));
+
+        let links = ExtractLinks::extract(&url, &document)?;
+        let mut internal: Vec<&str> = links.internal.iter().map(|l| l.url.as_str()).collect();
+        internal.sort();
+
+        assert_eq!(
+            internal,
+            ["https://example.com/app/foo.html", "https://example.com/root.html"]
+        );
+        assert_eq!(links.anchor[0].url, "https://example.com/app/#section");
+        assert_eq!(links.frames[0].url, "https://example.com/app/embed.html");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_images_are_extracted() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/pages/index.html")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <img src="logo.png" alt="Logo" loading="lazy">
+                <img srcset="/hero-480.jpg 480w, /hero-800.jpg 800w" alt="">
+                <img src="https://cdn.example.org/banner.webp">
+                <img src="data:image/gif;base64,R0lGODlhAQABAAAAACw=">
+                <img src="logo.png" alt="Logo again">
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+
+        assert_eq!(
+            links.images,
+            [
+                ImageInfo {
+                    url: "https://example.com/pages/logo.png".to_string(),
+                    alt: Some("Logo".to_string()),
+                    loading: Some("lazy".to_string()),
+                },
+                ImageInfo {
+                    url: "https://example.com/hero-480.jpg".to_string(),
+                    alt: Some(String::new()),
+                    loading: None,
+                },
+                ImageInfo {
+                    url: "https://cdn.example.org/banner.webp".to_string(),
+                    alt: None,
+                    loading: None,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames_are_extracted() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/pages/index.html")?;
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <iframe src="embed/content.html" title="Embedded content"></iframe>
+                <iframe src="https://video.example.org/player/1"></iframe>
+                <iframe src="about:blank"></iframe>
+                <iframe src="data:text/html,<p>inline</p>"></iframe>
+                <iframe src=""></iframe>
+            </body></html>"#,
+        );
+
+        let links = ExtractLinks::extract(&url, &document)?;
+        let mut frames: Vec<&str> = links.frames.iter().map(|f| f.url.as_str()).collect();
+        frames.sort();
+
+        assert_eq!(
+            frames,
+            [
+                "https://example.com/pages/embed/content.html",
+                "https://video.example.org/player/1",
+            ]
+        );
+        assert!(links.internal.is_empty());
+
+        let document = scraper::Html::parse_document(
+            r#"<html><frameset cols="25%,75%">
+                <frame src="/frames/menu.html" name="menu">
+                <frame src="content.html" name="content">
+            </frameset></html>"#,
+        );
+        let links = ExtractLinks::extract(&url, &document)?;
+        let mut frames: Vec<&str> = links.frames.iter().map(|f| f.url.as_str()).collect();
+        frames.sort();
+
+        assert_eq!(
+            frames,
+            [
+                "https://example.com/frames/menu.html",
+                "https://example.com/pages/content.html",
+            ]
+        );
+
+        Ok(())
+    }
 }
@@ -1,9 +1,12 @@
+use serde::Serialize;
 use std::collections::HashMap;
 use tracing::debug;
 use url::Url;
 
+use crate::fetch::{RateLimiter, send_following_redirects};
+
 /// Basic metadata about the page
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct BasicMetadata {
     /// The page title from the `<title>` tag
     pub title: Option<String>,
@@ -18,7 +21,7 @@ pub struct BasicMetadata {
 }
 
 /// Crawler and SEO related metadata
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SeoMetadata {
     /// Robots directive from the `robots` meta tag (e.g., "index, follow")
     pub robots: Option<String>,
@@ -30,10 +33,26 @@ pub struct SeoMetadata {
     pub publisher: Option<String>,
     /// The creator of the page content
     pub creator: Option<String>,
+    /// Self-declared content rating from the `rating` or `content-rating` meta tag (e.g., "adult")
+    pub content_rating: Option<String>,
+}
+
+/// Content rating values that mark a page as adult, compared case-insensitively
+/// Includes the RTA ("Restricted To Adults") label
+const ADULT_RATINGS: [&str; 4] = ["adult", "mature", "restricted", "rta-5042-1996-1400-1577-rta"];
+
+impl SeoMetadata {
+    /// Returns true when the page declares an adult content rating
+    pub fn is_adult(&self) -> bool {
+        self.content_rating.as_deref().is_some_and(|rating| {
+            let rating = rating.to_lowercase();
+            ADULT_RATINGS.iter().any(|adult| rating.contains(adult))
+        })
+    }
 }
 
 /// Open Graph metadata for social media sharing
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct OpenGraphMetadata {
     /// The type of content (e.g., "website", "article")
     pub og_type: Option<String>,
@@ -45,14 +64,55 @@ pub struct OpenGraphMetadata {
     pub og_url: Option<Url>,
     /// The image URL for preview when shared
     pub og_image: Option<Url>,
+    /// Every `og:image` declared on the page, with its structured properties
+    pub og_images: Vec<OpenGraphImage>,
     /// The name of the website
     pub og_site_name: Option<String>,
     /// The locale of the content (e.g., "en_US")
     pub og_locale: Option<String>,
 }
 
+/// A single `og:image` declaration and the `og:image:*` properties that follow it
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenGraphImage {
+    /// The image URL from `og:image` (or `og:image:url`)
+    pub url: Url,
+    /// The image width in pixels from `og:image:width`
+    pub width: Option<u32>,
+    /// The image height in pixels from `og:image:height`
+    pub height: Option<u32>,
+    /// The alternative text from `og:image:alt`
+    pub alt: Option<String>,
+    /// The MIME type from `og:image:type` (e.g., "image/png")
+    #[serde(rename = "type")]
+    pub mime_type: Option<String>,
+}
+
+impl OpenGraphImage {
+    /// Sends a HEAD request for the image and returns its `Content-Length`, if the server reports one
+    pub async fn verify(
+        &self,
+        client: &reqwest::Client,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> anyhow::Result<Option<u64>> {
+        debug!(url = %self.url, "Verifying og:image");
+        if let Some(rate_limiter) = rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let response = send_following_redirects(client, reqwest::Method::HEAD, &self.url)
+            .await?
+            .error_for_status()?;
+
+        Ok(response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok()))
+    }
+}
+
 /// Twitter Card metadata for Twitter sharing
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct TwitterCardMetadata {
     /// The type of Twitter card (e.g., "summary", "summary_large_image")
     pub twitter_card: Option<String>,
@@ -67,7 +127,7 @@ pub struct TwitterCardMetadata {
 }
 
 /// Viewport and mobile metadata
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ViewportMetadata {
     /// Viewport settings for responsive design (e.g., "width=device-width, initial-scale=1.0")
     pub viewport: Option<String>,
@@ -80,7 +140,7 @@ pub struct ViewportMetadata {
 }
 
 /// Link relationships
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct LinkMetadata {
     /// The canonical URL of the page to prevent duplicate content issues
     pub canonical: Option<Url>,
@@ -96,8 +156,62 @@ pub struct LinkMetadata {
     pub apple_touch_icon: Option<Url>,
 }
 
+/// Site-level identity declared through schema.org `Organization` and `WebSite` JSON-LD
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SiteIdentity {
+    /// The organization name, falling back to the website name
+    pub name: Option<String>,
+    /// The organization or website URL
+    pub url: Option<String>,
+    /// The organization logo URL (a plain URL or an `ImageObject`)
+    pub logo: Option<String>,
+    /// Profiles of the same entity on other sites, from `sameAs`
+    pub same_as: Vec<String>,
+    /// The site search URL template from the `WebSite` `SearchAction`
+    pub search_url_template: Option<String>,
+}
+
+/// Aggregate rating declared through schema.org `AggregateRating`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RatingInfo {
+    /// The rating value as declared on the page
+    pub rating_value: f64,
+    /// The best possible rating, when declared
+    pub best_rating: Option<f64>,
+    /// The rating on a 0-5 scale (equal to `rating_value` when no `bestRating` is declared)
+    pub normalized_rating: f64,
+    /// Number of reviews, falling back to `ratingCount`
+    pub review_count: Option<u64>,
+    /// Where the rating was found ("json-ld" or "microdata")
+    pub source: String,
+}
+
+impl RatingInfo {
+    fn new(rating_value: f64, best_rating: Option<f64>, review_count: Option<u64>, source: &str) -> Self {
+        let normalized_rating = match best_rating {
+            Some(best) if best > 0.0 => rating_value / best * 5.0,
+            _ => rating_value,
+        };
+
+        RatingInfo {
+            rating_value,
+            best_rating,
+            normalized_rating,
+            review_count,
+            source: source.to_string(),
+        }
+    }
+}
+
+/// Indexing and link-following permissions from the `robots` meta tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RobotsDirectives {
+    pub index: bool,
+    pub follow: bool,
+}
+
 /// Complete page metadata combining all metadata types
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct PageMetadata {
     /// Basic page information (title, description, language, etc.)
     pub basic: BasicMetadata,
@@ -111,9 +225,40 @@ pub struct PageMetadata {
     pub viewport: ViewportMetadata,
     /// Link relationships and alternate versions
     pub links: LinkMetadata,
+    /// Aggregate rating and review count from JSON-LD or microdata
+    pub ratings: Option<RatingInfo>,
+    /// Organization/WebSite identity from JSON-LD, usually only present on the homepage
+    pub site_identity: Option<SiteIdentity>,
 }
 
 impl PageMetadata {
+    /// Parses the `robots` meta tag case-insensitively; both permissions default to true
+    /// `none` is shorthand for `noindex, nofollow` and `all` for `index, follow`
+    pub fn robots_directives(&self) -> RobotsDirectives {
+        let mut directives = RobotsDirectives {
+            index: true,
+            follow: true,
+        };
+
+        let Some(robots) = &self.seo.robots else {
+            return directives;
+        };
+
+        for directive in robots.split(',').map(|d| d.trim().to_lowercase()) {
+            match directive.as_str() {
+                "noindex" => directives.index = false,
+                "nofollow" => directives.follow = false,
+                "none" => {
+                    directives.index = false;
+                    directives.follow = false;
+                }
+                _ => {}
+            }
+        }
+
+        directives
+    }
+
     /// Extracts metadata from an HTML document
     #[tracing::instrument(skip(document))]
     pub fn extract(document: &scraper::Html) -> anyhow::Result<Self> {
@@ -135,6 +280,7 @@ impl PageMetadata {
         metadata.seo.author = Self::extract_meta_content(document, "name", "author");
         metadata.seo.publisher = Self::extract_meta_content(document, "name", "publisher");
         metadata.seo.creator = Self::extract_meta_content(document, "name", "creator");
+        metadata.seo.content_rating = Self::extract_content_rating(document);
 
         // Extract Open Graph metadata
         debug!("Extracting Open Graph metadata");
@@ -144,6 +290,7 @@ impl PageMetadata {
             Self::extract_meta_property(document, "og:description");
         metadata.open_graph.og_url = Self::extract_url_from_property(document, "og:url");
         metadata.open_graph.og_image = Self::extract_url_from_property(document, "og:image");
+        metadata.open_graph.og_images = Self::extract_og_images(document);
         metadata.open_graph.og_site_name = Self::extract_meta_property(document, "og:site_name");
         metadata.open_graph.og_locale = Self::extract_meta_property(document, "og:locale");
 
@@ -179,6 +326,13 @@ impl PageMetadata {
         metadata.links.icon = Self::extract_link_href(document, "icon");
         metadata.links.apple_touch_icon = Self::extract_link_href(document, "apple-touch-icon");
 
+        // Extract structured data
+        debug!("Extracting structured data");
+        let json_ld = Self::extract_json_ld(document);
+        metadata.ratings = Self::extract_json_ld_rating(&json_ld)
+            .or_else(|| Self::extract_microdata_rating(document));
+        metadata.site_identity = Self::extract_json_ld_site_identity(&json_ld);
+
         debug!("Metadata extraction completed successfully");
         Ok(metadata)
     }
@@ -222,6 +376,19 @@ impl PageMetadata {
         result
     }
 
+    /// Extracts the content rating, matching the meta name case-insensitively since the
+    /// RTA label is conventionally written as `<meta name="RATING">`
+    fn extract_content_rating(document: &scraper::Html) -> Option<String> {
+        ["rating", "content-rating"].iter().find_map(|name| {
+            let selector = scraper::Selector::parse(&format!("meta[name=\"{}\" i]", name)).ok()?;
+            document
+                .select(&selector)
+                .filter_map(|el| el.value().attr("content"))
+                .map(|s| s.trim().to_string())
+                .find(|s| !s.is_empty())
+        })
+    }
+
     /// Extracts meta property content (for Open Graph)
     #[tracing::instrument(skip(document))]
     fn extract_meta_property(document: &scraper::Html, property: &str) -> Option<String> {
@@ -243,6 +410,70 @@ impl PageMetadata {
         result
     }
 
+    /// Extracts every `og:image` along with its structured properties
+    /// Per the Open Graph protocol, `og:image:*` properties apply to the most recent `og:image`
+    #[tracing::instrument(skip(document))]
+    fn extract_og_images(document: &scraper::Html) -> Vec<OpenGraphImage> {
+        debug!("Extracting og:image declarations");
+        let mut images: Vec<OpenGraphImage> = Vec::new();
+        let selector = match scraper::Selector::parse("meta[property^=\"og:image\"]") {
+            Ok(s) => s,
+            Err(e) => {
+                debug!(error = %e, "Failed to parse og:image selector");
+                return images;
+            }
+        };
+
+        // Tracks whether the latest og:image was usable, so that its properties
+        // are not attached to the previous image when its URL fails to parse
+        let mut current_is_valid = false;
+
+        for element in document.select(&selector) {
+            let (Some(property), Some(content)) =
+                (element.value().attr("property"), element.value().attr("content"))
+            else {
+                continue;
+            };
+            let content = content.trim();
+
+            match property {
+                "og:image" | "og:image:url" => match Url::parse(content) {
+                    Ok(url) => {
+                        debug!(url = %url, "og:image found");
+                        images.push(OpenGraphImage {
+                            url,
+                            width: None,
+                            height: None,
+                            alt: None,
+                            mime_type: None,
+                        });
+                        current_is_valid = true;
+                    }
+                    Err(e) => {
+                        debug!(url = %content, error = %e, "Failed to parse og:image URL");
+                        current_is_valid = false;
+                    }
+                },
+                _ => {
+                    let Some(image) = images.last_mut().filter(|_| current_is_valid) else {
+                        debug!(property = %property, "Ignoring og:image property without a preceding image");
+                        continue;
+                    };
+                    match property {
+                        "og:image:width" => image.width = content.parse().ok(),
+                        "og:image:height" => image.height = content.parse().ok(),
+                        "og:image:alt" => image.alt = Some(content.to_string()),
+                        "og:image:type" => image.mime_type = Some(content.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        debug!(count = images.len(), "og:image extraction completed");
+        images
+    }
+
     /// Extracts keywords as a vector of strings
     #[tracing::instrument(skip(document))]
     fn extract_keywords(document: &scraper::Html) -> Option<Vec<String>> {
@@ -280,7 +511,7 @@ impl PageMetadata {
     fn extract_canonical_from_link(document: &scraper::Html) -> Option<Url> {
         debug!("Extracting canonical URL from link tag");
         let selector = scraper::Selector::parse("link[rel=\"canonical\"]").ok()?;
-        let result = document
+        document
             .select(&selector)
             .next()
             .and_then(|el| el.value().attr("href"))
@@ -293,9 +524,7 @@ impl PageMetadata {
                     debug!(href = %href, error = %e, "Failed to parse canonical URL");
                     None
                 }
-            });
-
-        result
+            })
     }
 
     /// Extracts URL from meta property
@@ -372,7 +601,7 @@ impl PageMetadata {
     fn extract_link_href(document: &scraper::Html, rel: &str) -> Option<Url> {
         debug!(rel = %rel, "Extracting link href");
         let selector = scraper::Selector::parse(&format!("link[rel=\"{}\"]", rel)).ok()?;
-        let result = document
+        document
             .select(&selector)
             .next()
             .and_then(|el| el.value().attr("href"))
@@ -385,8 +614,417 @@ impl PageMetadata {
                     debug!(rel = %rel, href = %href, error = %e, "Failed to parse link URL");
                     None
                 }
+            })
+    }
+
+    /// Parses every `<script type="application/ld+json">` block, skipping invalid JSON
+    #[tracing::instrument(skip(document))]
+    fn extract_json_ld(document: &scraper::Html) -> Vec<serde_json::Value> {
+        debug!("Extracting JSON-LD blocks");
+        let selector = match scraper::Selector::parse("script[type=\"application/ld+json\"]") {
+            Ok(s) => s,
+            Err(e) => {
+                debug!(error = %e, "Failed to parse JSON-LD selector");
+                return Vec::new();
+            }
+        };
+
+        let blocks: Vec<serde_json::Value> = document
+            .select(&selector)
+            .filter_map(|el| {
+                let text = el.text().collect::<String>();
+                match serde_json::from_str(&text) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        debug!(error = %e, "Failed to parse JSON-LD block");
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        debug!(count = blocks.len(), "JSON-LD extraction completed");
+        blocks
+    }
+
+    /// Finds the first JSON-LD node whose `@type` matches, searching nested objects, arrays and `@graph`
+    fn find_json_ld_type<'a>(value: &'a serde_json::Value, type_name: &str) -> Option<&'a serde_json::Value> {
+        match value {
+            serde_json::Value::Object(map) => {
+                let matches = match map.get("@type") {
+                    Some(serde_json::Value::String(t)) => t == type_name,
+                    Some(serde_json::Value::Array(types)) => {
+                        types.iter().any(|t| t.as_str() == Some(type_name))
+                    }
+                    _ => false,
+                };
+                if matches {
+                    return Some(value);
+                }
+                map.values().find_map(|v| Self::find_json_ld_type(v, type_name))
+            }
+            serde_json::Value::Array(items) => {
+                items.iter().find_map(|v| Self::find_json_ld_type(v, type_name))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads a JSON-LD number that may be encoded as a string (e.g., `"4.5"`)
+    fn json_ld_number(value: Option<&serde_json::Value>) -> Option<f64> {
+        match value? {
+            serde_json::Value::Number(n) => n.as_f64(),
+            serde_json::Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Extracts an `AggregateRating` from parsed JSON-LD blocks
+    #[tracing::instrument(skip(json_ld))]
+    fn extract_json_ld_rating(json_ld: &[serde_json::Value]) -> Option<RatingInfo> {
+        debug!("Extracting JSON-LD AggregateRating");
+        let rating = json_ld
+            .iter()
+            .find_map(|block| Self::find_json_ld_type(block, "AggregateRating"))?;
+
+        let rating_value = Self::json_ld_number(rating.get("ratingValue"))?;
+        let best_rating = Self::json_ld_number(rating.get("bestRating"));
+        let review_count = Self::json_ld_number(rating.get("reviewCount"))
+            .or_else(|| Self::json_ld_number(rating.get("ratingCount")))
+            .map(|count| count as u64);
+
+        debug!(rating_value = rating_value, "JSON-LD AggregateRating found");
+        Some(RatingInfo::new(rating_value, best_rating, review_count, "json-ld"))
+    }
+
+    /// Extracts site identity from `Organization` and `WebSite` JSON-LD nodes
+    #[tracing::instrument(skip(json_ld))]
+    fn extract_json_ld_site_identity(json_ld: &[serde_json::Value]) -> Option<SiteIdentity> {
+        debug!("Extracting JSON-LD site identity");
+        let find = |type_name| {
+            json_ld
+                .iter()
+                .find_map(|block| Self::find_json_ld_type(block, type_name))
+        };
+        let organization = find("Organization");
+        let website = find("WebSite");
+        if organization.is_none() && website.is_none() {
+            return None;
+        }
+
+        let string = |node: Option<&serde_json::Value>, key: &str| {
+            node.and_then(|n| n.get(key))
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+        };
+        // Schema.org URLs may be plain strings or nodes carrying a `url` (e.g., `ImageObject`)
+        let url_of = |value: &serde_json::Value| match value {
+            serde_json::Value::String(s) => Some(s.trim().to_string()),
+            serde_json::Value::Object(_) => value.get("url").and_then(|u| u.as_str()).map(str::to_string),
+            _ => None,
+        };
+
+        let same_as = match organization.and_then(|o| o.get("sameAs")) {
+            Some(serde_json::Value::String(s)) => vec![s.trim().to_string()],
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let search_url_template = website
+            .and_then(|w| w.get("potentialAction"))
+            .and_then(|action| Self::find_json_ld_type(action, "SearchAction"))
+            .and_then(|action| match action.get("target")? {
+                serde_json::Value::String(s) => Some(s.trim().to_string()),
+                target => target.get("urlTemplate")?.as_str().map(str::to_string),
             });
 
-        result
+        Some(SiteIdentity {
+            name: string(organization, "name").or_else(|| string(website, "name")),
+            url: string(organization, "url").or_else(|| string(website, "url")),
+            logo: organization.and_then(|o| o.get("logo")).and_then(url_of),
+            same_as,
+            search_url_template,
+        })
+    }
+
+    /// Extracts an `AggregateRating` declared with microdata (`itemprop` attributes)
+    #[tracing::instrument(skip(document))]
+    fn extract_microdata_rating(document: &scraper::Html) -> Option<RatingInfo> {
+        debug!("Extracting microdata AggregateRating");
+        let scope_selector =
+            scraper::Selector::parse("[itemtype*=\"schema.org/AggregateRating\"]").ok()?;
+        let scope = document.select(&scope_selector).next()?;
+
+        let property = |name: &str| -> Option<f64> {
+            let selector = scraper::Selector::parse(&format!("[itemprop=\"{}\"]", name)).ok()?;
+            let element = scope.select(&selector).next()?;
+            element
+                .value()
+                .attr("content")
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| element.text().collect::<String>())
+                .trim()
+                .parse()
+                .ok()
+        };
+
+        let rating_value = property("ratingValue")?;
+        let review_count = property("reviewCount")
+            .or_else(|| property("ratingCount"))
+            .map(|count| count as u64);
+
+        debug!(rating_value = rating_value, "Microdata AggregateRating found");
+        Some(RatingInfo::new(rating_value, property("bestRating"), review_count, "microdata"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::extract_metadata::{PageMetadata, RobotsDirectives};
+
+    #[test]
+    fn test_serialized_json_shape() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(include_str!("../test-site/pages/metadata.html"));
+        let mut metadata = PageMetadata::extract(&document)?;
+        metadata.open_graph.og_images = PageMetadata::extract(&scraper::Html::parse_document(
+            r#"<html><head>
+                <meta property="og:image" content="https://example.com/cover.png">
+                <meta property="og:image:type" content="image/png">
+            </head></html>"#,
+        ))?
+        .open_graph
+        .og_images;
+
+        let json = serde_json::to_value(&metadata)?;
+
+        assert_eq!(json["basic"]["title"], "Metadata Test Page - Web Crawler Testing");
+        assert_eq!(json["seo"]["canonical"], "https://example.com/pages/metadata.html");
+        assert_eq!(
+            json["links"]["alternate_languages"],
+            serde_json::json!({
+                "es": "https://example.com/es/pages/metadata.html",
+                "fr": "https://example.com/fr/pages/metadata.html",
+            })
+        );
+        assert_eq!(json["links"]["prev"], serde_json::Value::Null);
+        assert_eq!(
+            json["open_graph"]["og_images"],
+            serde_json::json!([{
+                "url": "https://example.com/cover.png",
+                "width": null,
+                "height": null,
+                "alt": null,
+                "type": "image/png",
+            }])
+        );
+        assert_eq!(json["viewport"]["apple_mobile_web_app_capable"], true);
+        assert_eq!(json["ratings"], serde_json::Value::Null);
+        assert_eq!(
+            serde_json::to_value(metadata.robots_directives())?,
+            serde_json::json!({ "index": true, "follow": true })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_ld_site_identity() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(include_str!("../test-site/pages/organization.html"));
+
+        let identity = PageMetadata::extract(&document)?
+            .site_identity
+            .expect("site identity should be extracted");
+
+        assert_eq!(identity.name.as_deref(), Some("Example Company"));
+        assert_eq!(identity.url.as_deref(), Some("https://example.com/"));
+        assert_eq!(identity.logo.as_deref(), Some("https://example.com/images/logo.png"));
+        assert_eq!(
+            identity.same_as,
+            ["https://twitter.com/example", "https://www.linkedin.com/company/example"]
+        );
+        assert_eq!(
+            identity.search_url_template.as_deref(),
+            Some("https://example.com/search?q={search_term_string}")
+        );
+
+        let document = scraper::Html::parse_document("<html><head><title>Plain</title></head></html>");
+        assert!(PageMetadata::extract(&document)?.site_identity.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_og_image_properties() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><head>
+                <meta property="og:image" content="https://example.com/images/cover.png">
+                <meta property="og:image:width" content="1200">
+                <meta property="og:image:height" content="630">
+                <meta property="og:image:alt" content="A cover image">
+                <meta property="og:image:type" content="image/png">
+            </head></html>"#,
+        );
+
+        let metadata = PageMetadata::extract(&document)?;
+        let images = &metadata.open_graph.og_images;
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].url.as_str(), "https://example.com/images/cover.png");
+        assert_eq!(images[0].width, Some(1200));
+        assert_eq!(images[0].height, Some(630));
+        assert_eq!(images[0].alt.as_deref(), Some("A cover image"));
+        assert_eq!(images[0].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(
+            metadata.open_graph.og_image.map(|u| u.to_string()),
+            Some("https://example.com/images/cover.png".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_og_images() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><head>
+                <meta property="og:image" content="https://example.com/first.jpg">
+                <meta property="og:image:width" content="800">
+                <meta property="og:image" content="/relative.jpg">
+                <meta property="og:image:width" content="10">
+                <meta property="og:image" content="https://example.com/second.jpg">
+                <meta property="og:image:alt" content="Second image">
+            </head></html>"#,
+        );
+
+        let images = PageMetadata::extract(&document)?.open_graph.og_images;
+
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].url.as_str(), "https://example.com/first.jpg");
+        assert_eq!(images[0].width, Some(800));
+        assert_eq!(images[0].alt, None);
+        assert_eq!(images[1].url.as_str(), "https://example.com/second.jpg");
+        assert_eq!(images[1].width, None);
+        assert_eq!(images[1].alt.as_deref(), Some("Second image"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_ld_aggregate_rating() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><head><script type="application/ld+json">
+                {
+                    "@context": "https://schema.org",
+                    "@type": "Product",
+                    "name": "Test Product",
+                    "aggregateRating": {
+                        "@type": "AggregateRating",
+                        "ratingValue": "8",
+                        "bestRating": "10",
+                        "reviewCount": 42
+                    }
+                }
+            </script></head></html>"#,
+        );
+
+        let ratings = PageMetadata::extract(&document)?
+            .ratings
+            .expect("rating should be extracted");
+
+        assert_eq!(ratings.rating_value, 8.0);
+        assert_eq!(ratings.best_rating, Some(10.0));
+        assert_eq!(ratings.normalized_rating, 4.0);
+        assert_eq!(ratings.review_count, Some(42));
+        assert_eq!(ratings.source, "json-ld");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_microdata_aggregate_rating() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><body>
+                <div itemscope itemtype="https://schema.org/Product">
+                    <span itemprop="name">Test Product</span>
+                    <div itemprop="aggregateRating" itemscope itemtype="https://schema.org/AggregateRating">
+                        Rated <span itemprop="ratingValue">4.5</span>/5
+                        based on <span itemprop="reviewCount">120</span> reviews
+                    </div>
+                </div>
+            </body></html>"#,
+        );
+
+        let ratings = PageMetadata::extract(&document)?
+            .ratings
+            .expect("rating should be extracted");
+
+        assert_eq!(ratings.rating_value, 4.5);
+        assert_eq!(ratings.best_rating, None);
+        assert_eq!(ratings.normalized_rating, 4.5);
+        assert_eq!(ratings.review_count, Some(120));
+        assert_eq!(ratings.source, "microdata");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_rating() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><head><meta name="rating" content="adult"></head></html>"#,
+        );
+        let metadata = PageMetadata::extract(&document)?;
+        assert_eq!(metadata.seo.content_rating.as_deref(), Some("adult"));
+        assert!(metadata.seo.is_adult());
+
+        let document = scraper::Html::parse_document(
+            r#"<html><head><meta name="RATING" content="RTA-5042-1996-1400-1577-RTA"></head></html>"#,
+        );
+        assert!(PageMetadata::extract(&document)?.seo.is_adult());
+
+        let document = scraper::Html::parse_document(
+            r#"<html><head><meta name="content-rating" content="General"></head></html>"#,
+        );
+        let metadata = PageMetadata::extract(&document)?;
+        assert_eq!(metadata.seo.content_rating.as_deref(), Some("General"));
+        assert!(!metadata.seo.is_adult());
+
+        let document = scraper::Html::parse_document("<html><head></head></html>");
+        let metadata = PageMetadata::extract(&document)?;
+        assert_eq!(metadata.seo.content_rating, None);
+        assert!(!metadata.seo.is_adult());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_robots_directives() -> anyhow::Result<()> {
+        let cases = [
+            (None, true, true),
+            (Some("index, follow"), true, true),
+            (Some("NOINDEX"), false, true),
+            (Some("noindex, nofollow"), false, false),
+            (Some("NoFollow, noarchive"), true, false),
+            (Some("none"), false, false),
+            (Some("all"), true, true),
+            (Some(" max-snippet:-1 , nofollow "), true, false),
+        ];
+
+        for (robots, index, follow) in cases {
+            let html = match robots {
+                Some(content) => format!(r#"<html><head><meta name="robots" content="{content}"></head></html>"#),
+                None => "<html><head></head></html>".to_string(),
+            };
+            let metadata = PageMetadata::extract(&scraper::Html::parse_document(&html))?;
+            assert_eq!(
+                metadata.robots_directives(),
+                RobotsDirectives { index, follow },
+                "robots {robots:?}"
+            );
+        }
+
+        Ok(())
     }
 }
@@ -1,8 +1,16 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, SystemTime};
 
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
-use reqwest::{Client, StatusCode};
+use clap::ValueEnum;
+use reqwest::{
+    Client, Method, Response, StatusCode,
+    header::{HeaderMap, LOCATION, RETRY_AFTER, USER_AGENT},
+};
 use serde::Deserialize;
 use tracing::{debug, error, info, warn};
 use url::Url;
@@ -13,25 +21,316 @@ pub struct FetchedPage {
     pub final_url: Url,
     pub status_code: u16,
     pub content_type: Option<String>,
+    /// Headers of the final response, lowercase names; repeated headers are joined with ", "
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
     pub html_content: String,
+    /// The server answered 200 with an empty or whitespace-only body
+    #[serde(default)]
+    pub empty_body: bool,
     #[serde(skip)]
     pub parsed_html: Option<scraper::Html>,
     pub fetched_duration_ms: u128,
     pub timestamp: DateTime<Utc>,
 }
 
+/// Error returned when the server answers with a status that is neither a success nor a redirect
+/// Callers can recover the status with `anyhow::Error::downcast_ref::<HttpStatusError>()`
+#[derive(Debug, Clone)]
+pub struct HttpStatusError {
+    pub status: StatusCode,
+    /// Number of retries attempted before giving up (0 when the status is not retryable)
+    pub retries: i32,
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = self.status.canonical_reason().unwrap_or("Unknown");
+        if self.retries > 0 {
+            write!(f, "HTTP Error {} after {} retries: {}", self.status, self.retries, reason)
+        } else {
+            write!(f, "HTTP Error {}: {}", self.status, reason)
+        }
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// Caps the number of requests per second across every fetch sharing it, whatever the host
+/// Works as a token bucket holding a single token, so requests are spaced evenly without bursts
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// Earliest moment the next request may start
+    next_slot: Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests.max(1),
+            next_slot: Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Waits until the next request slot, reserving it so concurrent callers queue up behind each other
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(PoisonError::into_inner);
+            let slot = (*next_slot).max(tokio::time::Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Ceiling on the total number of retries shared by every fetch of a CLI invocation
+/// Once spent, failures are returned immediately instead of being retried
+#[derive(Debug, Default)]
+pub struct RetryBudget {
+    /// Retries left, or `None` for an unlimited budget
+    remaining: Option<AtomicUsize>,
+    exhausted_logged: AtomicBool,
+}
+
+impl RetryBudget {
+    pub fn new(max_retries_total: Option<usize>) -> Self {
+        Self {
+            remaining: max_retries_total.map(AtomicUsize::new),
+            exhausted_logged: AtomicBool::new(false),
+        }
+    }
+
+    /// Takes one retry from the budget, returning false when none are left
+    pub fn try_acquire(&self) -> bool {
+        let Some(remaining) = &self.remaining else {
+            return true;
+        };
+
+        let acquired = remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+
+        if !acquired && !self.exhausted_logged.swap(true, Ordering::SeqCst) {
+            warn!("Global retry budget exhausted, further failures will not be retried");
+        }
+
+        acquired
+    }
+}
+
+/// How the delay between retries grows with each attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backoff {
+    /// `base * 2^(attempt - 1)`
+    #[default]
+    Exponential,
+    /// `base * attempt`
+    Linear,
+    /// `base` for every attempt
+    Constant,
+}
+
+/// Randomization applied on top of the backoff delay to spread out concurrent retries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Jitter {
+    /// Uniform in `[0, delay]`
+    Full,
+    /// `delay / 2` plus uniform in `[0, delay / 2]`
+    Equal,
+    /// The delay as computed
+    #[default]
+    None,
+}
+
+/// Per-invocation settings for [`fetch_page`]
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub max_redirects: i32,
+    pub max_retries: i32,
+    /// Base delay the backoff strategy scales from
+    pub retry_delay: Duration,
+    pub backoff: Backoff,
+    pub jitter: Jitter,
+    /// Retry a 200 response whose body is empty or whitespace-only, as it is often transient
+    pub retry_empty_body: bool,
+    /// Source of every random decision, shared by all clones so one seed reproduces a run
+    pub rng: Arc<Mutex<fastrand::Rng>>,
+    /// User-Agent overrides keyed by lowercase host; other hosts use the client's default
+    pub host_user_agents: HashMap<String, String>,
+    /// Media types parsed as HTML; a trailing `/*` matches a whole type (e.g. `text/*`)
+    pub content_types: Vec<String>,
+    /// Request rate cap shared by all clones, applied to every request including retries and redirect hops
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            max_redirects: 5,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            backoff: Backoff::default(),
+            jitter: Jitter::default(),
+            retry_empty_body: false,
+            rng: Arc::new(Mutex::new(fastrand::Rng::new())),
+            host_user_agents: HashMap::new(),
+            content_types: vec!["text/html".to_string(), "application/xhtml+xml".to_string()],
+            rate_limiter: None,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Returns the User-Agent configured for the host of `url`, if any
+    pub fn user_agent_for(&self, url: &Url) -> Option<&str> {
+        let host = url.host_str()?.to_lowercase();
+        self.host_user_agents.get(&host).map(String::as_str)
+    }
+
+    /// Returns true when the media type of `content_type` is in the allowlist (case-insensitive)
+    pub fn is_allowed_content_type(&self, content_type: &str) -> bool {
+        let media_type = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        self.content_types.iter().any(|allowed| {
+            let allowed = allowed.trim().to_lowercase();
+            match allowed.strip_suffix("/*") {
+                Some(type_) => media_type.split('/').next() == Some(type_),
+                None => media_type == allowed,
+            }
+        })
+    }
+
+    /// Delay before retry number `attempt` (starting at 1), jitter included
+    pub fn retry_delay_for(&self, attempt: i32) -> Duration {
+        let random = self.rng.lock().map(|mut rng| rng.f64()).unwrap_or(0.5);
+        self.jittered(self.backoff_delay(attempt), random)
+    }
+
+    /// Delay before retry number `attempt` (starting at 1) as given by the backoff strategy alone
+    fn backoff_delay(&self, attempt: i32) -> Duration {
+        let attempt = attempt.max(1) as u32;
+        match self.backoff {
+            Backoff::Exponential => self
+                .retry_delay
+                .saturating_mul(2u32.saturating_pow(attempt - 1)),
+            Backoff::Linear => self.retry_delay.saturating_mul(attempt),
+            Backoff::Constant => self.retry_delay,
+        }
+    }
+
+    /// Applies the jitter mode to `delay`, with `random` drawn uniformly from `[0, 1)`
+    fn jittered(&self, delay: Duration, random: f64) -> Duration {
+        match self.jitter {
+            Jitter::Full => delay.mul_f64(random),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(random),
+            Jitter::None => delay,
+        }
+    }
+}
+
+/// Flattens `headers` into one entry per name, joining repeated values with ", " as RFC 9110 allows
+/// Values that are not visible ASCII are decoded lossily
+fn header_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        map.entry(name.to_string())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    map
+}
+
+/// How far into the body a `<meta charset>` declaration is looked for, as in the HTML spec
+const CHARSET_SNIFF_LIMIT: usize = 1024;
+
+/// Decodes a response body using the charset from `content_type`, then from a `<meta charset>`
+/// (or `http-equiv` Content-Type) near the start of the body, falling back to UTF-8
+/// Undecodable bytes become U+FFFD rather than failing the fetch
+pub fn decode_body(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset_label)
+        .or_else(|| {
+            let head = String::from_utf8_lossy(&bytes[..bytes.len().min(CHARSET_SNIFF_LIMIT)]);
+            charset_label(&head)
+        })
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+
+    debug!(encoding = encoding.name(), "Decoding response body");
+    // A byte order mark overrides any declared charset
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// Returns the value following the first `charset=` in `text`, without quotes
+fn charset_label(text: &str) -> Option<String> {
+    let lower = text.to_ascii_lowercase();
+    let start = lower.find("charset=")? + "charset=".len();
+    let label: String = lower[start..]
+        .trim_start_matches(['"', '\'', ' '])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    (!label.is_empty()).then_some(label)
+}
+
+/// Longest `Retry-After` the crawler is willing to wait; longer requests are capped
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Parses a `Retry-After` value, either delay-seconds or an HTTP-date, capped at [`MAX_RETRY_AFTER`]
+/// Dates in the past yield a zero delay
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+            (date - now).to_std().unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// Redirects followed by [`send_following_redirects`], the minimum RFC 9309 asks of robots.txt fetches
+const MAX_FOLLOWED_REDIRECTS: usize = 5;
+
+/// Sends a `method` request to `url`, following up to [`MAX_FOLLOWED_REDIRECTS`] redirects itself
+/// The CLI client does not follow redirects so [`fetch_page`] can detect loops; robots.txt, sitemap
+/// and og:image requests go through here instead
+pub async fn send_following_redirects(client: &Client, method: Method, url: &Url) -> anyhow::Result<Response> {
+    let mut current_url = url.clone();
+    for _ in 0..=MAX_FOLLOWED_REDIRECTS {
+        let response = client.request(method.clone(), current_url.clone()).send().await?;
+        let location = response.headers().get(LOCATION).and_then(|location| location.to_str().ok());
+        let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+            return Ok(response);
+        };
+        current_url = current_url
+            .join(location)
+            .map_err(|_| anyhow!("Invalid redirect URL: {}", location))?;
+        debug!(status = response.status().as_u16(), location = %current_url, "Following redirect");
+    }
+    Err(anyhow!("Too many redirects fetching {}", url))
+}
+
 pub async fn fetch_page(
     client: &Client,
     url: &Url,
-    max_redirects: i32,
-    max_retries: i32,
-    retry_delay: Duration,
+    options: &FetchOptions,
+    retry_budget: &RetryBudget,
 ) -> anyhow::Result<FetchedPage> {
     let mut current_url = url.clone();
     let mut retry_count = 0;
     let mut redirect_count = 0;
-    let mut max_redirects = max_redirects;
-    let mut retry_delay = retry_delay;
+    let mut max_redirects = options.max_redirects;
+    let max_retries = options.max_retries;
+    // Every URL visited in this redirect chain, to tell a loop apart from a long chain
+    let mut redirect_chain = HashSet::from([url.clone()]);
 
     let now = SystemTime::now();
 
@@ -48,7 +347,17 @@ pub async fn fetch_page(
             current_url, retry_count, max_retries
         );
 
-        let response = match client.get(current_url.clone()).send().await {
+        if let Some(rate_limiter) = &options.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let mut request = client.get(current_url.clone());
+        // Resolved per hop, so a redirect to another host picks up that host's User-Agent
+        if let Some(user_agent) = options.user_agent_for(&current_url) {
+            request = request.header(USER_AGENT, user_agent);
+        }
+
+        let response = match request.send().await {
             Ok(resp) => {
                 debug!(
                     "Received response from: {}, status: {}",
@@ -58,8 +367,9 @@ pub async fn fetch_page(
                 resp
             }
             Err(e) => {
-                if retry_count < max_retries {
+                if retry_count < max_retries && retry_budget.try_acquire() {
                     retry_count += 1;
+                    let retry_delay = options.retry_delay_for(retry_count);
                     warn!(
                       error = %e,
                       retry = retry_count,
@@ -68,17 +378,16 @@ pub async fn fetch_page(
                       "Network error, retrying..."
                     );
                     tokio::time::sleep(retry_delay).await;
-                    retry_delay = Duration::from_millis(retry_delay.as_millis() as u64 * 2);
                     continue;
                 } else {
                     error!(
                       error = %e,
-                      max_retries = max_retries,
+                      retries = retry_count,
                       "Failed to fetch after max retries"
                     );
                     return Err(anyhow!(
                         "Failed to fetch after {} retries: {}",
-                        max_retries,
+                        retry_count,
                         e
                     ));
                 }
@@ -102,13 +411,44 @@ pub async fn fetch_page(
                 debug!("Content-Type: {}", content_type);
 
                 let status_code = response.status();
+                // Differs from current_url when the client followed redirects itself
+                let final_url = response.url().clone();
+                let headers = header_map(response.headers());
 
-                let html = response.text().await?;
+                let bytes = response.bytes().await?;
+                let html = decode_body(&bytes, Some(&content_type));
                 debug!("Parsed HTML content, size: {} bytes", html.len());
 
+                let empty_body = html.trim().is_empty();
+                if empty_body {
+                    if options.retry_empty_body
+                        && retry_count < max_retries
+                        && retry_budget.try_acquire()
+                    {
+                        retry_count += 1;
+                        let retry_delay = options.retry_delay_for(retry_count);
+                        warn!(
+                            url = %current_url,
+                            retry = retry_count,
+                            max_retries = max_retries,
+                            delay_ms = retry_delay.as_millis(),
+                            "Empty body, retrying..."
+                        );
+                        tokio::time::sleep(retry_delay).await;
+                        continue;
+                    }
+                    warn!(url = %current_url, retries = retry_count, "Server returned 200 with an empty body");
+                }
+
                 let timestamp = Utc::now();
 
-                let html_document = scraper::Html::parse_document(&html);
+                // Other media types are returned as-is so callers can still count and size them
+                let html_document = if options.is_allowed_content_type(&content_type) {
+                    Some(scraper::Html::parse_document(&html))
+                } else {
+                    info!(url = %current_url, content_type = %content_type, "Skipping HTML parsing for content type");
+                    None
+                };
 
                 info!(
                   url = %current_url,
@@ -119,16 +459,23 @@ pub async fn fetch_page(
 
                 return Ok(FetchedPage {
                     url: url.clone(),
-                    final_url: current_url.clone(),
+                    final_url,
                     status_code: status_code.as_u16(),
                     content_type: Some(content_type),
+                    headers,
                     html_content: html.clone(),
-                    parsed_html: Some(html_document),
+                    empty_body,
+                    parsed_html: html_document,
                     fetched_duration_ms: duration.as_millis(),
                     timestamp,
                 });
             }
-            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => {
+            // Only GETs are issued, so 303/307/308 need no method handling
+            StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT => {
                 let location = response
                     .headers()
                     .get("Location")
@@ -145,6 +492,11 @@ pub async fn fetch_page(
                     .or_else(|_| current_url.join(location))
                     .map_err(|_| anyhow!("Invalid redirect URL: {}", location))?;
 
+                if !redirect_chain.insert(current_url.clone()) {
+                    error!(url = %url, target = %current_url, "Redirect loop detected");
+                    return Err(anyhow!("Redirect loop detected: {}", current_url));
+                }
+
                 max_redirects -= 1;
                 redirect_count += 1;
                 retry_count = 0;
@@ -156,31 +508,38 @@ pub async fn fetch_page(
                   "Redirect processed"
                 );
             }
-            StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-                if retry_count < max_retries {
+            StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::TOO_MANY_REQUESTS => {
+                if retry_count < max_retries && retry_budget.try_acquire() {
                     retry_count += 1;
+                    // The server's own Retry-After takes precedence over the computed backoff
+                    let retry_delay = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| parse_retry_after(value, Utc::now()))
+                        .unwrap_or_else(|| options.retry_delay_for(retry_count));
                     warn!(
                         status = response.status().as_u16(),
                         retry = retry_count,
                         max_retries = max_retries,
                         delay_ms = retry_delay.as_millis(),
-                        "Server error, retrying..."
+                        "Retryable HTTP status, retrying..."
                     );
                     tokio::time::sleep(retry_delay).await;
-                    retry_delay = Duration::from_millis(retry_delay.as_millis() as u64 * 2);
                     continue;
                 } else {
                     error!(
                         status = response.status().as_u16(),
-                        max_retries = max_retries,
-                        "Server error after max retries"
+                        retries = retry_count,
+                        "Retryable HTTP status after max retries"
                     );
-                    return Err(anyhow!(
-                        "HTTP Error {} after {} retries: {}",
-                        response.status(),
-                        max_retries,
-                        response.status().canonical_reason().unwrap_or("Unknown")
-                    ));
+                    return Err(HttpStatusError {
+                        status: response.status(),
+                        retries: retry_count,
+                    }
+                    .into());
                 }
             }
             _ => {
@@ -189,12 +548,499 @@ pub async fn fetch_page(
                     reason = response.status().canonical_reason(),
                     "HTTP error"
                 );
-                return Err(anyhow!(
-                    "HTTP Error {}: {}",
-                    response.status(),
-                    response.status().canonical_reason().unwrap_or("Unknown")
-                ));
+                return Err(HttpStatusError {
+                    status: response.status(),
+                    retries: 0,
+                }
+                .into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use reqwest::{Client, redirect::Policy};
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::fetch::{
+        Backoff, FetchOptions, HttpStatusError, Jitter, RateLimiter, RetryBudget, decode_body, fetch_page,
+        parse_retry_after,
+    };
+    use crate::utils::{MockResponse, MockServer};
+
+    fn fast_retries() -> FetchOptions {
+        FetchOptions {
+            retry_delay: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_strategies() {
+        let options = |backoff| FetchOptions {
+            retry_delay: Duration::from_millis(100),
+            backoff,
+            ..Default::default()
+        };
+        let delays = |backoff| -> Vec<u128> {
+            (1..=4)
+                .map(|attempt| options(backoff).retry_delay_for(attempt).as_millis())
+                .collect()
+        };
+
+        assert_eq!(delays(Backoff::Exponential), [100, 200, 400, 800]);
+        assert_eq!(delays(Backoff::Linear), [100, 200, 300, 400]);
+        assert_eq!(delays(Backoff::Constant), [100, 100, 100, 100]);
+    }
+
+    #[test]
+    fn test_jitter_modes() {
+        let options = |jitter| FetchOptions {
+            jitter,
+            ..Default::default()
+        };
+        let delay = Duration::from_millis(1000);
+
+        assert_eq!(options(Jitter::Full).jittered(delay, 0.0), Duration::ZERO);
+        assert_eq!(options(Jitter::Full).jittered(delay, 0.25), Duration::from_millis(250));
+        assert_eq!(options(Jitter::Equal).jittered(delay, 0.0), Duration::from_millis(500));
+        assert_eq!(options(Jitter::Equal).jittered(delay, 0.5), Duration::from_millis(750));
+        assert_eq!(options(Jitter::None).jittered(delay, 0.9), delay);
+
+        // Third retry of a 1s exponential backoff computes 4s before jitter
+        for _ in 0..100 {
+            let full = options(Jitter::Full).retry_delay_for(3);
+            assert!(full <= Duration::from_secs(4), "{full:?}");
+            let equal = options(Jitter::Equal).retry_delay_for(3);
+            assert!((Duration::from_secs(2)..=Duration::from_secs(4)).contains(&equal), "{equal:?}");
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_jitter() {
+        let options = |seed| FetchOptions {
+            jitter: Jitter::Full,
+            rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(seed))),
+            ..Default::default()
+        };
+        let delays = |options: FetchOptions| -> Vec<Duration> {
+            // Clones share the RNG, as the fetches of one invocation do
+            (1..=5)
+                .map(|attempt| options.clone().retry_delay_for(attempt))
+                .collect()
+        };
+
+        assert_eq!(delays(options(42)), delays(options(42)));
+        assert_ne!(delays(options(42)), delays(options(43)));
+    }
+
+    #[test]
+    fn test_decode_body_charsets() {
+        const LATIN1: &[u8] = include_bytes!("../test-site/pages/latin1.html");
+
+        // Declared by the <meta charset> only
+        let html = decode_body(LATIN1, Some("text/html"));
+        assert!(html.contains("<title>Café crème brûlée</title>"), "{html}");
+        assert!(html.contains("Déjà vu à la façon de Noël."));
+
+        // The header wins over the body
+        let html = decode_body("Ã©".as_bytes(), Some("text/html; charset=\"ISO-8859-1\""));
+        // WHATWG maps the ISO-8859-1 label to windows-1252, where 0x83 is "ƒ"
+        assert_eq!(html, "ÃƒÂ©");
+
+        let html = decode_body(b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1252\">\x93ok\x94", None);
+        assert!(html.ends_with("\u{201c}ok\u{201d}"), "{html}");
+
+        // Unknown or missing charsets fall back to UTF-8
+        assert_eq!(decode_body("é".as_bytes(), Some("text/html; charset=bogus")), "é");
+        assert_eq!(decode_body("é".as_bytes(), None), "é");
+    }
+
+    #[test]
+    fn test_content_type_allowlist() {
+        let options = FetchOptions::default();
+        assert!(options.is_allowed_content_type("text/html"));
+        assert!(options.is_allowed_content_type("Text/HTML; charset=utf-8"));
+        assert!(options.is_allowed_content_type("application/xhtml+xml"));
+        assert!(!options.is_allowed_content_type("application/pdf"));
+        assert!(!options.is_allowed_content_type("text/plain"));
+
+        let options = FetchOptions {
+            content_types: vec!["text/*".to_string()],
+            ..Default::default()
+        };
+        assert!(options.is_allowed_content_type("text/plain"));
+        assert!(!options.is_allowed_content_type("application/xhtml+xml"));
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_content_type_is_not_parsed() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let content_type = if request.path == "/report.pdf" {
+                "application/pdf"
+            } else {
+                "text/html"
+            };
+            MockResponse::new(200)
+                .header("Content-Type", content_type)
+                .body("%PDF-1.7 or <html></html>")
+        })
+        .await?;
+        let client = Client::new();
+
+        let page = fetch_page(&client, &server.url.join("/report.pdf")?, &fast_retries(), &RetryBudget::default()).await?;
+        assert_eq!(page.status_code, 200);
+        assert!(page.parsed_html.is_none());
+        assert!(!page.html_content.is_empty());
+
+        let page = fetch_page(&client, &server.url.join("/page")?, &fast_retries(), &RetryBudget::default()).await?;
+        assert!(page.parsed_html.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_decodes_latin1_page() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html; charset=iso-8859-1")
+                .body(include_bytes!("../test-site/pages/latin1.html").to_vec())
+        })
+        .await?;
+
+        let page = fetch_page(&Client::new(), &server.url, &fast_retries(), &RetryBudget::default()).await?;
+
+        assert!(page.html_content.contains("Café crème brûlée"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap();
+
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(60)));
+        assert_eq!(parse_retry_after(" 5 ", now), Some(Duration::from_secs(5)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Thu, 22 Oct 2015 07:28:00 GMT", now), Some(Duration::from_secs(60)));
+        assert_eq!(parse_retry_after("-1", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_overrides_backoff() -> anyhow::Result<()> {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let server = MockServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::new(503).header("Retry-After", "0")
+            } else {
+                MockResponse::new(200)
+                    .header("Content-Type", "text/html")
+                    .body("<html></html>")
             }
+        })
+        .await?;
+        // A backoff this long would time the test out if Retry-After were ignored
+        let options = FetchOptions {
+            retry_delay: Duration::from_secs(3600),
+            ..Default::default()
+        };
+
+        let page = tokio::time::timeout(
+            Duration::from_secs(5),
+            fetch_page(&Client::new(), &server.url, &options, &RetryBudget::default()),
+        )
+        .await??;
+
+        assert_eq!(page.status_code, 200);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_too_many_requests_is_retried() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| MockResponse::new(429)).await?;
+
+        let result = fetch_page(&Client::new(), &server.url, &fast_retries(), &RetryBudget::default()).await;
+
+        let error = result.expect_err("429 should fail once retries are exhausted");
+        let status_error = error.downcast_ref::<HttpStatusError>().expect("HttpStatusError");
+        assert_eq!(status_error.status.as_u16(), 429);
+        assert_eq!(status_error.retries, 3);
+        assert_eq!(error.to_string(), "HTTP Error 429 Too Many Requests after 3 retries: Too Many Requests");
+        assert_eq!(server.requests().len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_follows_303_307_308_redirects() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/start" => MockResponse::new(308).header("Location", "/see-other"),
+            "/see-other" => MockResponse::new(303).header("Location", "/temporary"),
+            "/temporary" => MockResponse::new(307).header("Location", "/final"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html></html>"),
+        })
+        .await?;
+        let url = server.url.join("/start")?;
+        // Without this reqwest follows the redirects itself and fetch_page never sees them
+        let client = Client::builder().redirect(Policy::none()).build()?;
+
+        let page = fetch_page(&client, &url, &fast_retries(), &RetryBudget::default()).await?;
+
+        assert_eq!(page.status_code, 200);
+        assert_eq!(page.final_url, server.url.join("/final")?);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/start", "/see-other", "/temporary", "/final"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_final_response_headers_are_kept() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/start" => MockResponse::new(301)
+                .header("Location", "/final")
+                .header("X-Redirect", "yes"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .header("Cache-Control", "no-cache")
+                .header("Vary", "Accept")
+                .header("Vary", "Accept-Encoding")
+                .body("<html></html>"),
+        })
+        .await?;
+        let client = Client::builder().redirect(Policy::none()).build()?;
+
+        let page = fetch_page(&client, &server.url.join("/start")?, &fast_retries(), &RetryBudget::default()).await?;
+
+        assert_eq!(page.status_code, 200);
+        assert_eq!(page.headers.get("cache-control").map(String::as_str), Some("no-cache"));
+        assert_eq!(page.headers.get("vary").map(String::as_str), Some("Accept, Accept-Encoding"));
+        assert!(!page.headers.contains_key("x-redirect"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_final_url_when_client_follows_redirects() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/start" => MockResponse::new(301).header("Location", "/middle"),
+            "/middle" => MockResponse::new(302).header("Location", "/final"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html></html>"),
+        })
+        .await?;
+        let url = server.url.join("/start")?;
+
+        let page = fetch_page(&Client::new(), &url, &fast_retries(), &RetryBudget::default()).await?;
+
+        assert_eq!(page.url, url);
+        assert_eq!(page.final_url, server.url.join("/final")?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_concurrent_fetches() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html></html>")
+        })
+        .await?;
+        let options = FetchOptions {
+            rate_limiter: Some(Arc::new(RateLimiter::per_second(20))),
+            ..fast_retries()
+        };
+        let client = Client::new();
+        let started = std::time::Instant::now();
+
+        let fetches: Vec<_> = (0..5)
+            .map(|i| {
+                let (client, options, url) = (client.clone(), options.clone(), server.url.join(&format!("/{i}")));
+                // FetchedPage holds a non-Send parsed document, so only the status leaves the task
+                tokio::spawn(async move {
+                    let page = fetch_page(&client, &url?, &options, &RetryBudget::default()).await?;
+                    anyhow::Ok(page.status_code)
+                })
+            })
+            .collect();
+        for fetch in fetches {
+            fetch.await??;
         }
+
+        // The first request goes out immediately, the other four wait 50ms each
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "finished in {elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "finished in {elapsed:?}");
+        assert_eq!(server.requests().len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_redirect_loop_is_detected() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/a" => MockResponse::new(302).header("Location", "/b"),
+            _ => MockResponse::new(301).header("Location", "/a"),
+        })
+        .await?;
+        let client = Client::builder().redirect(Policy::none()).build()?;
+
+        let error = fetch_page(&client, &server.url.join("/a")?, &fast_retries(), &RetryBudget::default())
+            .await
+            .expect_err("a redirect loop should fail");
+
+        assert_eq!(error.to_string(), format!("Redirect loop detected: {}", server.url.join("/a")?));
+        assert_eq!(server.requests().len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_stops_retries_once_spent() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(503)
+                .header("Content-Type", "text/plain")
+                .body("busy")
+        })
+        .await?;
+        let client = Client::new();
+        let retry_budget = RetryBudget::new(Some(2));
+
+        for path in ["a", "b", "c"] {
+            let url = server.url.join(path)?;
+            let result = fetch_page(&client, &url, &fast_retries(), &retry_budget).await;
+            assert!(result.is_err());
+        }
+
+        // "a" spends the whole budget (1 attempt + 2 retries), "b" and "c" get one attempt each
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/a", "/a", "/a", "/b", "/c"]);
+        assert!(!retry_budget.try_acquire());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_retry_budget() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| MockResponse::new(500)).await?;
+        let client = Client::new();
+        let retry_budget = RetryBudget::new(None);
+
+        let url = server.url.join("flaky")?;
+        let result = fetch_page(&client, &url, &fast_retries(), &retry_budget).await;
+
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_empty_body_is_flagged() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(" \n\t ")
+        })
+        .await?;
+        let client = Client::new();
+        let url = server.url.join("/empty")?;
+
+        let page = fetch_page(&client, &url, &fast_retries(), &RetryBudget::default()).await?;
+        assert!(page.empty_body);
+        assert_eq!(server.requests().len(), 1);
+
+        let options = FetchOptions {
+            retry_empty_body: true,
+            ..fast_retries()
+        };
+        let page = fetch_page(&client, &url, &options, &RetryBudget::default()).await?;
+        assert!(page.empty_body);
+        assert_eq!(server.requests().len(), 1 + 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_empty_body_retry_recovers() -> anyhow::Result<()> {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let server = MockServer::start(move |_| {
+            let body = if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                ""
+            } else {
+                "<html><body>Ready</body></html>"
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let options = FetchOptions {
+            retry_empty_body: true,
+            ..fast_retries()
+        };
+
+        let page = fetch_page(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert!(!page.empty_body);
+        assert!(page.html_content.contains("Ready"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_host_user_agent_override() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html></html>")
+        })
+        .await?;
+        let client = Client::builder().user_agent("Global-Agent/1.0").build()?;
+
+        // The mock server is reachable both as 127.0.0.1 and as localhost
+        let port = server.url.port().unwrap();
+        let options = FetchOptions {
+            host_user_agents: HashMap::from([("localhost".to_string(), "Host-Agent/2.0".to_string())]),
+            ..Default::default()
+        };
+
+        let url = format!("http://LocalHost:{port}/configured").parse()?;
+        fetch_page(&client, &url, &options, &RetryBudget::default()).await?;
+        let url = server.url.join("/other")?;
+        fetch_page(&client, &url, &options, &RetryBudget::default()).await?;
+
+        let agents: Vec<Option<String>> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("user-agent").map(str::to_string))
+            .collect();
+        assert_eq!(
+            agents,
+            [Some("Host-Agent/2.0".to_string()), Some("Global-Agent/1.0".to_string())]
+        );
+
+        Ok(())
     }
 }
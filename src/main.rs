@@ -5,9 +5,13 @@ use crate::{cli::execute_commands, commands::Cli};
 mod check_robots;
 mod cli;
 mod commands;
+mod doctor;
 mod extract_links;
 mod extract_metadata;
 mod fetch;
+mod profile;
+mod seo_report;
+mod sitemap;
 mod utils;
 mod printer;
 
@@ -2,54 +2,52 @@ pub fn pretty_printer(value: serde_json::Value) -> anyhow::Result<String> {
     let mut output = String::new();
     
     // For root objects with a single key that's an object, unwrap it
-    if let serde_json::Value::Object(map) = &value {
-        if map.len() == 1 {
-            if let Some((title, nested_val)) = map.iter().next() {
-                if let serde_json::Value::Object(nested_map) = nested_val {
-                    // Print header with the title
-                    output.push_str("╭─ ");
-                    output.push_str(title);
-                    output.push(' ');
-                    let line_width = 60;
-                    let current_len = output.lines().last().unwrap_or("").len();
-                    if current_len < line_width {
-                        output.push_str(&"─".repeat(line_width.saturating_sub(current_len)));
-                    }
+    if let serde_json::Value::Object(map) = &value
+        && map.len() == 1
+        && let Some((title, nested_val)) = map.iter().next()
+        && let serde_json::Value::Object(nested_map) = nested_val
+    {
+        // Print header with the title
+        output.push_str("╭─ ");
+        output.push_str(title);
+        output.push(' ');
+        let line_width = 60;
+        let current_len = output.lines().last().unwrap_or("").len();
+        if current_len < line_width {
+            output.push_str(&"─".repeat(line_width.saturating_sub(current_len)));
+        }
+        output.push('\n');
+        
+        // Print the nested object's entries directly
+        let entries: Vec<_> = nested_map.iter().collect();
+        let len = entries.len();
+        for (idx, (key, val)) in entries.iter().enumerate() {
+            let is_last = idx == len - 1;
+            let prefix = if is_last { "╰─" } else { "├─" };
+            
+            output.push_str(prefix);
+            output.push(' ');
+            output.push_str(key);
+            
+            match val {
+                serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+                    output.push_str(": ");
                     output.push('\n');
-                    
-                    // Print the nested object's entries directly
-                    let entries: Vec<_> = nested_map.iter().collect();
-                    let len = entries.len();
-                    for (idx, (key, val)) in entries.iter().enumerate() {
-                        let is_last = idx == len - 1;
-                        let prefix = if is_last { "╰─" } else { "├─" };
-                        
-                        output.push_str(prefix);
-                        output.push(' ');
-                        output.push_str(key);
-                        
-                        match val {
-                            serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
-                                output.push_str(": ");
-                                output.push('\n');
-                                format_value(val, &mut output, 1, false);
-                            }
-                            _ => {
-                                output.push_str(": ");
-                                format_value(val, &mut output, 0, false);
-                                output.push('\n');
-                            }
-                        }
-                    }
-                    
-                    // Print closing border
-                    output.push_str("╰");
-                    output.push_str(&"─".repeat(59));
+                    format_value(val, &mut output, 1, false);
+                }
+                _ => {
+                    output.push_str(": ");
+                    format_value(val, &mut output, 0, false);
                     output.push('\n');
-                    return Ok(output);
                 }
             }
         }
+        
+        // Print closing border
+        output.push('╰');
+        output.push_str(&"─".repeat(59));
+        output.push('\n');
+        return Ok(output);
     }
     
     // Fall back to normal formatting for other structures
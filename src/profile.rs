@@ -0,0 +1,1341 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::Client;
+use serde::Serialize;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    check_robots::{RobotsDecision, RobotsFetchResult, fetch_robots, fetch_robots_from},
+    extract_links::ExtractLinks,
+    extract_metadata::PageMetadata,
+    fetch::{FetchOptions, HttpStatusError, RetryBudget, fetch_page},
+    sitemap::{SiteMap, discover_sitemaps},
+    utils::normalize_url,
+};
+
+/// Upper bound on a robots.txt crawl-delay, so a hostile or mistaken value cannot stall sampling
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+/// How often a crawl-delay wait checks whether a shutdown was requested
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How a site is sampled by [`SiteProfile::sample`]
+#[derive(Debug, Clone)]
+pub struct ProfileOptions {
+    /// Maximum number of URLs to request
+    pub max_samples: usize,
+    /// Also sample same-site `<iframe>`/`<frame>` sources
+    pub follow_iframes: bool,
+    /// Seed the frontier with the same-site URLs listed in the site's sitemaps
+    pub use_sitemap: bool,
+    /// Maximum number of links followed away from a seed; `None` means unlimited
+    pub max_depth: Option<usize>,
+    /// Do not follow links from pages whose meta tags declare an adult content rating
+    pub skip_adult: bool,
+    /// Maximum number of example URLs kept per status code; `None` keeps them all
+    pub limit_per_status: Option<usize>,
+    /// URLs whose query string matches any of these are never enqueued (crawler-trap guard)
+    pub deny_query: Vec<Regex>,
+    /// When non-empty, only URLs matching at least one of these are enqueued
+    pub include: Vec<Regex>,
+    /// URLs matching any of these are never enqueued, even when they match `include`
+    pub exclude: Vec<Regex>,
+    /// Hosts besides the start URL's host whose links are followed
+    pub allow_domains: Vec<String>,
+    /// Hosts that are never enqueued, even the start URL's host or an allowed one
+    pub block_domains: Vec<String>,
+    /// Treat `/a/` and `/a` as the same URL when deduplicating
+    pub strip_trailing_slash: bool,
+    /// Probability of enqueueing each discovered link, rolled with `fetch.rng`; seeds are always enqueued
+    pub link_sample_rate: f64,
+    /// robots.txt location used to discover sitemaps, instead of the host root
+    pub robots_url: Option<Url>,
+    /// Settings for each page request
+    pub fetch: FetchOptions,
+    /// Minimum time between the starts of two requests to the same host, keyed by host (robots.txt crawl-delay)
+    /// A global `fetch.rate_limiter` still applies, so whichever of the two is stricter wins
+    pub crawl_delays: HashMap<String, Duration>,
+    /// robots.txt per host, fetched before sampling; URLs it disallows are skipped, and hosts missing from it allow everything
+    pub robots: HashMap<String, RobotsFetchResult>,
+    /// User-Agent matched against robots.txt groups on hosts without a `fetch.host_user_agents` override
+    pub user_agent: String,
+    /// Once set, no further URLs are taken from the frontier and the partial profile is returned
+    pub shutdown: Arc<AtomicBool>,
+}
+
+impl ProfileOptions {
+    /// Returns true when the query string of `url` matches one of the deny patterns
+    pub fn is_query_denied(&self, url: &Url) -> bool {
+        url.query()
+            .is_some_and(|query| self.deny_query.iter().any(|regex| regex.is_match(query)))
+    }
+
+    /// Returns true when `url` is on the start URL's host or an allowed host, and not on a blocked one
+    pub fn is_host_allowed(&self, url: &Url, start_url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        if self.block_domains.iter().any(|blocked| blocked.eq_ignore_ascii_case(host)) {
+            debug!(url = %url, "Not enqueueing URL on blocked domain");
+            return false;
+        }
+        start_url.host_str() == Some(host)
+            || self.allow_domains.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// Returns the crawl-delay for `host`, capped at [`MAX_CRAWL_DELAY`]
+    pub fn crawl_delay_for(&self, host: &str) -> Option<Duration> {
+        self.crawl_delays.get(host).map(|delay| (*delay).min(MAX_CRAWL_DELAY))
+    }
+
+    /// Returns false when the robots.txt of the host of `url` disallows it for our User-Agent
+    /// A robots.txt answering 403 or 5xx disallows the whole host, as in `check-robot`
+    pub fn is_robots_allowed(&self, url: &Url) -> bool {
+        let Some(robots) = url.host_str().and_then(|host| self.robots.get(&host.to_lowercase())) else {
+            return true;
+        };
+        match robots {
+            RobotsFetchResult::Success(robot) => {
+                let user_agent = self.fetch.user_agent_for(url).unwrap_or(&self.user_agent);
+                robot.check(url.as_str(), user_agent).allowed
+            }
+            RobotsFetchResult::NotFound => true,
+            RobotsFetchResult::Forbidden | RobotsFetchResult::Unreachable(_) => false,
+        }
+    }
+
+    /// Sleeps until `deadline`, returning false as soon as a shutdown is requested
+    async fn wait_unless_shutdown(&self, deadline: tokio::time::Instant) -> bool {
+        while tokio::time::Instant::now() < deadline {
+            if self.shutdown.load(Ordering::Relaxed) {
+                return false;
+            }
+            tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + SHUTDOWN_POLL_INTERVAL)).await;
+        }
+        !self.shutdown.load(Ordering::Relaxed)
+    }
+
+    /// Rolls the shared RNG to decide whether a discovered link is kept under `link_sample_rate`
+    pub fn sample_link(&self) -> bool {
+        if self.link_sample_rate >= 1.0 {
+            return true;
+        }
+        self.fetch
+            .rng
+            .lock()
+            .map(|mut rng| rng.f64() < self.link_sample_rate)
+            .unwrap_or(true)
+    }
+
+    /// Applies the URL filters: `deny_query`, then `exclude`, then `include`
+    /// The start URL is always sampled; only discovered URLs are filtered
+    pub fn should_enqueue(&self, url: &Url) -> bool {
+        if self.is_query_denied(url) {
+            debug!(url = %url, "Not enqueueing URL with denied query");
+            return false;
+        }
+        if self.exclude.iter().any(|regex| regex.is_match(url.as_str())) {
+            debug!(url = %url, "Not enqueueing excluded URL");
+            return false;
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|regex| regex.is_match(url.as_str())) {
+            debug!(url = %url, "Not enqueueing URL outside the include patterns");
+            return false;
+        }
+        true
+    }
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        Self {
+            max_samples: 20,
+            follow_iframes: false,
+            use_sitemap: false,
+            max_depth: None,
+            skip_adult: false,
+            limit_per_status: None,
+            deny_query: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            allow_domains: Vec::new(),
+            block_domains: Vec::new(),
+            strip_trailing_slash: false,
+            link_sample_rate: 1.0,
+            robots_url: None,
+            fetch: FetchOptions::default(),
+            crawl_delays: HashMap::new(),
+            robots: HashMap::new(),
+            user_agent: concat!("Marahuyo-Crawler/", env!("CARGO_PKG_VERSION")).to_string(),
+            shutdown: Arc::default(),
+        }
+    }
+}
+
+/// Sets `shutdown` on the first Ctrl-C so sampling winds down after the in-flight request
+/// A second Ctrl-C exits immediately
+pub async fn shutdown_on_ctrl_c(shutdown: Arc<AtomicBool>) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    warn!("Ctrl-C received, finishing the current request; press Ctrl-C again to abort");
+    shutdown.store(true, Ordering::Relaxed);
+
+    if tokio::signal::ctrl_c().await.is_ok() {
+        warn!("Second Ctrl-C received, aborting");
+        std::process::exit(130);
+    }
+}
+
+/// Summary of a shallow breadth-first sample of a site
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SiteProfile {
+    /// Number of URLs that were requested
+    pub sampled: usize,
+    /// Number of sampled URLs that could not be fetched
+    pub failed: usize,
+    /// Response count per content type, without parameters such as `charset`
+    pub content_types: BTreeMap<String, usize>,
+    /// Response count per HTTP status code
+    pub status_codes: BTreeMap<u16, usize>,
+    /// Example URLs per HTTP status code, capped by `limit_per_status`
+    pub urls_by_status: BTreeMap<u16, Vec<String>>,
+    /// Average body size in bytes over the successfully fetched pages
+    pub average_page_size: usize,
+    /// Sampled URLs whose same-host `<link rel="canonical">` points elsewhere, mapped to that canonical URL
+    pub canonical_urls: BTreeMap<String, String>,
+    /// Sampling stopped early because a shutdown was requested
+    pub interrupted: bool,
+    /// URLs taken from the frontier but skipped because robots.txt disallows them
+    pub robots_disallowed: usize,
+}
+
+impl SiteProfile {
+    /// Samples up to `max_samples` URLs breadth-first from `url`, following links on its own host
+    /// and on `allow_domains` only.
+    /// URLs disallowed by `robots` are skipped, and a start host whose robots.txt is forbidden or
+    /// unreachable is not sampled at all.
+    /// Links are not followed from pages whose robots meta tag says `nofollow`, nor when the
+    /// anchor itself carries `rel="nofollow"`.
+    /// The start URL and sitemap seeds are at depth 0; links found on a page at depth N are at N + 1.
+    /// Nothing is persisted: the frontier only lives for the duration of the call.
+    pub async fn sample(
+        client: &Client,
+        url: &Url,
+        options: &ProfileOptions,
+        retry_budget: &RetryBudget,
+    ) -> anyhow::Result<Self> {
+        let mut profile = SiteProfile::default();
+        let mut frontier = VecDeque::from([(url.clone(), 0)]);
+        let normalize = |url: &Url| normalize_url(url, options.strip_trailing_slash);
+        let mut seen = HashSet::from([normalize(url).to_string()]);
+        let mut total_size = 0;
+        // Start of the previous request per crawl-delayed host
+        let mut last_request: HashMap<String, tokio::time::Instant> = HashMap::new();
+        let max_samples = options.max_samples;
+
+        if let Some(robots @ (RobotsFetchResult::Forbidden | RobotsFetchResult::Unreachable(_))) =
+            url.host_str().and_then(|host| options.robots.get(&host.to_lowercase()))
+        {
+            return Err(anyhow::anyhow!("Refusing to sample {}: robots.txt is {:?}", url, robots));
+        }
+
+        debug!(url = %url, max_samples = max_samples, "Starting site profile");
+        for (host, delay) in &options.crawl_delays {
+            if *delay > MAX_CRAWL_DELAY {
+                warn!(host, delay_s = delay.as_secs_f64(), max_s = MAX_CRAWL_DELAY.as_secs(), "Capping robots.txt crawl-delay");
+            }
+        }
+
+        if options.use_sitemap {
+            for sitemap_url in Self::sitemap_urls(client, url, options).await {
+                if options.is_host_allowed(&sitemap_url, url)
+                    && options.should_enqueue(&sitemap_url)
+                    && seen.insert(normalize(&sitemap_url).to_string())
+                {
+                    frontier.push_back((normalize(&sitemap_url), 0));
+                }
+            }
+            debug!(frontier = frontier.len(), "Seeded frontier from sitemaps");
+        }
+
+        while let Some((current_url, depth)) = frontier.pop_front() {
+            if profile.sampled >= max_samples {
+                break;
+            }
+            if options.shutdown.load(Ordering::Relaxed) {
+                warn!(sampled = profile.sampled, pending = frontier.len() + 1, "Shutdown requested, stopping sampling");
+                profile.interrupted = true;
+                break;
+            }
+            if !options.is_robots_allowed(&current_url) {
+                debug!(url = %current_url, "Skipping URL disallowed by robots.txt");
+                profile.robots_disallowed += 1;
+                continue;
+            }
+
+            if let Some(host) = current_url.host_str()
+                && let Some(delay) = options.crawl_delay_for(host)
+            {
+                if let Some(last_start) = last_request.get(host)
+                    && !options.wait_unless_shutdown(*last_start + delay).await
+                {
+                    warn!(sampled = profile.sampled, pending = frontier.len() + 1, "Shutdown requested during crawl-delay, stopping sampling");
+                    profile.interrupted = true;
+                    break;
+                }
+                last_request.insert(host.to_string(), tokio::time::Instant::now());
+            }
+            profile.sampled += 1;
+
+            let page = match fetch_page(client, &current_url, &options.fetch, retry_budget).await {
+                Ok(page) => page,
+                Err(e) => {
+                    profile.failed += 1;
+                    if let Some(status_error) = e.downcast_ref::<HttpStatusError>() {
+                        profile.record_status(status_error.status.as_u16(), &current_url, options);
+                    }
+                    warn!(url = %current_url, error = %e, "Failed to sample URL");
+                    continue;
+                }
+            };
+
+            profile.record_status(page.status_code, &current_url, options);
+            // Links to where a redirect landed are the same page
+            seen.insert(normalize(&page.final_url).to_string());
+            total_size += page.html_content.len();
+
+            let content_type = page
+                .content_type
+                .as_deref()
+                .and_then(|c| c.split(';').next())
+                .map(|c| c.trim().to_lowercase())
+                .unwrap_or_else(|| "unknown".to_string());
+            *profile.content_types.entry(content_type).or_default() += 1;
+
+            // Only responses allowed by `fetch.content_types` are parsed, so only they yield links
+            if let Some(document) = &page.parsed_html {
+                let metadata = PageMetadata::extract(document)?;
+                // Cross-host canonicals are ignored so a page cannot hide another site's URLs
+                if let Some(canonical) = metadata.seo.canonical.as_ref().map(normalize)
+                    && canonical.host_str() == page.final_url.host_str()
+                    && canonical != normalize(&page.final_url)
+                {
+                    profile
+                        .canonical_urls
+                        .insert(page.final_url.to_string(), canonical.to_string());
+                    if !seen.insert(canonical.to_string()) {
+                        debug!(url = %current_url, canonical = %canonical, "Not following links from duplicate of a seen canonical page");
+                        continue;
+                    }
+                }
+
+                if options.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                    debug!(url = %current_url, depth, "Not following links beyond max depth");
+                    continue;
+                }
+                if options.skip_adult && metadata.seo.is_adult() {
+                    debug!(url = %current_url, "Not following links from adult-rated page");
+                    continue;
+                }
+                if !metadata.robots_directives().follow {
+                    debug!(url = %current_url, "Not following links from nofollow page");
+                    continue;
+                }
+
+                let links = ExtractLinks::extract(&page.final_url, document)?;
+                let frames = links.frames.into_iter().filter(|frame| {
+                    options.follow_iframes
+                        && Url::parse(&frame.url)
+                            .is_ok_and(|frame_url| frame_url.host_str() == page.final_url.host_str())
+                });
+                let anchors = links.internal.into_iter().chain(links.external);
+                let anchors = anchors.filter(|link| !link.is_nofollow());
+                for link in anchors.chain(frames) {
+                    match Url::parse(&link.url).map(|link_url| normalize(&link_url)) {
+                        Ok(link_url)
+                            if seen.insert(link_url.to_string())
+                                && options.is_host_allowed(&link_url, url)
+                                && options.should_enqueue(&link_url)
+                                && options.sample_link() =>
+                        {
+                            frontier.push_back((link_url, depth + 1))
+                        }
+                        Ok(_) => {}
+                        Err(e) => debug!(url = %link.url, error = %e, "Skipping unparseable link"),
+                    }
+                }
+            }
+        }
+
+        let fetched = profile.sampled - profile.failed;
+        profile.average_page_size = total_size.checked_div(fetched).unwrap_or(0);
+
+        debug!(
+            sampled = profile.sampled,
+            failed = profile.failed,
+            "Site profile complete"
+        );
+
+        Ok(profile)
+    }
+
+    /// Checks the start URL against its host's robots.txt, warning when it is disallowed
+    /// Sampling skips a disallowed start URL like any other, so the warning explains an empty profile
+    /// A robots.txt that cannot be fetched allows everything, as in `check-robot`
+    /// The robots.txt fetch result is returned too, so sampling can enforce it and honor its crawl-delay
+    pub async fn check_seed_robots(
+        client: &Client,
+        url: &Url,
+        user_agent: &str,
+        fetch_options: &FetchOptions,
+        robots_url: Option<&Url>,
+    ) -> (RobotsDecision, Option<RobotsFetchResult>) {
+        let rate_limiter = fetch_options.rate_limiter.as_deref();
+        let robots = match robots_url {
+            Some(robots_url) => fetch_robots_from(client, robots_url.as_str(), rate_limiter).await,
+            None => fetch_robots(client, url, rate_limiter).await,
+        };
+
+        let decision = match &robots {
+            Ok(RobotsFetchResult::Success(robot)) => robot.check(url.as_str(), user_agent),
+            Ok(RobotsFetchResult::Forbidden) => RobotsDecision {
+                allowed: false,
+                reason: "robots.txt returned 403 Forbidden".to_string(),
+            },
+            Ok(RobotsFetchResult::Unreachable(status)) => RobotsDecision {
+                allowed: false,
+                reason: format!("robots.txt is unreachable (HTTP {})", status),
+            },
+            Ok(RobotsFetchResult::NotFound) => RobotsDecision {
+                allowed: true,
+                reason: "No robots.txt found".to_string(),
+            },
+            Err(e) => {
+                warn!(url = %url, error = %e, "Failed to fetch robots.txt for the start URL");
+                RobotsDecision {
+                    allowed: true,
+                    reason: format!("robots.txt could not be fetched: {}", e),
+                }
+            }
+        };
+
+        if !decision.allowed {
+            warn!(url = %url, user_agent, reason = %decision.reason, "Start URL is disallowed by robots.txt");
+        }
+        (decision, robots.ok())
+    }
+
+    /// Fetches robots.txt for each of `hosts`, reached with the scheme and port of `url`
+    /// Hosts whose robots.txt cannot be fetched are left out, which allows everything as elsewhere
+    pub async fn fetch_host_robots(
+        client: &Client,
+        url: &Url,
+        hosts: &[String],
+        fetch_options: &FetchOptions,
+    ) -> HashMap<String, RobotsFetchResult> {
+        let mut robots = HashMap::new();
+        for host in hosts {
+            let mut host_url = url.clone();
+            if let Err(e) = host_url.set_host(Some(host)) {
+                warn!(host, error = %e, "Skipping robots.txt for invalid host");
+                continue;
+            }
+            match fetch_robots(client, &host_url, fetch_options.rate_limiter.as_deref()).await {
+                Ok(result) => {
+                    robots.insert(host.to_lowercase(), result);
+                }
+                Err(e) => warn!(host, error = %e, "Failed to fetch robots.txt"),
+            }
+        }
+        robots
+    }
+
+    /// Counts `status` and keeps `url` as an example unless the status already has enough
+    fn record_status(&mut self, status: u16, url: &Url, options: &ProfileOptions) {
+        *self.status_codes.entry(status).or_default() += 1;
+        let examples = self.urls_by_status.entry(status).or_default();
+        if options.limit_per_status.is_none_or(|limit| examples.len() < limit) {
+            examples.push(url.to_string());
+        }
+    }
+
+    /// Collects the `<loc>` of every sitemap advertised for the host of `url`, one index level deep
+    /// Sitemaps that cannot be loaded are logged and skipped so sampling can still start from `url`
+    async fn sitemap_urls(client: &Client, url: &Url, options: &ProfileOptions) -> Vec<Url> {
+        let rate_limiter = options.fetch.rate_limiter.as_deref();
+        let discovery = match discover_sitemaps(client, url, options.robots_url.as_ref(), rate_limiter).await {
+            Ok(discovery) => discovery,
+            Err(e) => {
+                warn!(url = %url, error = %e, "Failed to discover sitemaps");
+                return Vec::new();
+            }
+        };
+
+        let mut locs = Vec::new();
+        for sitemap in discovery.sitemaps {
+            // robots.txt may list sitemaps relative to the host
+            let Ok(sitemap_url) = url.join(&sitemap) else {
+                warn!(sitemap = %sitemap, "Skipping unparseable sitemap URL");
+                continue;
+            };
+            match SiteMap::fetch_recursive(client, &sitemap_url, 1, rate_limiter).await {
+                Ok(entries) => locs.extend(entries.into_iter().map(|entry| entry.loc)),
+                Err(e) => warn!(url = %sitemap_url, error = %e, "Failed to load sitemap"),
+            }
+        }
+        locs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::time::Duration;
+
+    use regex::Regex;
+    use reqwest::Client;
+    use url::Url;
+
+    use crate::check_robots::RobotsFetchResult;
+    use crate::fetch::{FetchOptions, RateLimiter, RetryBudget};
+    use crate::profile::{ProfileOptions, SiteProfile};
+    use crate::utils::{MockResponse, MockServer, PythonServer};
+
+    #[tokio::test]
+    async fn test_profile_content_type_distribution() -> anyhow::Result<()> {
+        let mut python_server = PythonServer::new(9002);
+        python_server.run()?;
+
+        let client = Client::new();
+        let url = Url::parse("http://localhost:9002/profile/index.html")?;
+        let options = ProfileOptions {
+            max_samples: 10,
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&client, &url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 5);
+        assert_eq!(profile.failed, 1);
+        assert_eq!(profile.content_types.get("text/html"), Some(&2));
+        assert_eq!(profile.content_types.get("text/plain"), Some(&1));
+        assert_eq!(profile.content_types.get("application/json"), Some(&1));
+        assert_eq!(profile.status_codes.get(&200), Some(&4));
+        assert_eq!(profile.status_codes.get(&404), Some(&1));
+        assert!(profile.average_page_size > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_follows_links_in_allowed_content_types() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body(r#"<a href="/next">Next</a>"#),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body("<html><body>Leaf</body></html>"),
+        })
+        .await?;
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 1);
+
+        let options = ProfileOptions {
+            fetch: FetchOptions {
+                content_types: vec!["text/*".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 2);
+        assert_eq!(profile.content_types.get("text/plain"), Some(&1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_follows_same_site_iframes() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body>
+                    <iframe src="/embed.html"></iframe>
+                    <iframe src="https://video.example.org/player"></iframe>
+                </body></html>"#,
+                _ => "<html><body>Embedded</body></html>",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let client = Client::new();
+
+        let mut options = ProfileOptions::default();
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 1);
+
+        options.follow_iframes = true;
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 2);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/", "/embed.html"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_skips_iframes_on_other_ip_hosts() -> anyhow::Result<()> {
+        // IP hosts have no domain(), which must not make every IP frame look same-site
+        let server = MockServer::start(|request| {
+            let port = request.header("Host").and_then(|host| host.rsplit(':').next()).unwrap_or_default();
+            let body = match request.path.as_str() {
+                "/" => format!(r#"<html><body><iframe src="http://127.0.0.2:{port}/embed.html"></iframe></body></html>"#),
+                _ => "<html><body>Embedded</body></html>".to_string(),
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let options = ProfileOptions {
+            follow_iframes: true,
+            allow_domains: vec!["127.0.0.2".to_string()],
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 1);
+        assert_eq!(profile.failed, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_seeds_from_sitemap() -> anyhow::Result<()> {
+        // Sitemap <loc>s must be absolute, so the handler learns the server origin once it is bound
+        let origin: Arc<OnceLock<String>> = Arc::default();
+        let handler_origin = origin.clone();
+        let server = MockServer::start(move |request| {
+            let origin = handler_origin.get().unwrap();
+            match request.path.as_str() {
+                "/robots.txt" => MockResponse::new(200).body("User-agent: *\nSitemap: /sitemap.xml\n"),
+                "/sitemap.xml" => MockResponse::new(200).body(format!(
+                    "<urlset>
+                        <url><loc>{origin}/orphan.html</loc></url>
+                        <url><loc>{origin}/</loc></url>
+                        <url><loc>https://elsewhere.example.com/page</loc></url>
+                    </urlset>"
+                )),
+                _ => MockResponse::new(200)
+                    .header("Content-Type", "text/html")
+                    .body("<html><body>No links</body></html>"),
+            }
+        })
+        .await?;
+        origin.set(server.url.as_str().trim_end_matches('/').to_string()).unwrap();
+        let client = Client::new();
+
+        let options = ProfileOptions {
+            use_sitemap: true,
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+
+        // The start URL and the orphan page; the duplicate and the other host are dropped
+        assert_eq!(profile.sampled, 2);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/robots.txt", "/sitemap.xml", "/", "/orphan.html"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_max_depth() -> anyhow::Result<()> {
+        // A chain of pages: / -> /1 -> /2 -> /3
+        let server = MockServer::start(|request| {
+            let next = match request.path.as_str() {
+                "/" => "/1",
+                "/1" => "/2",
+                "/2" => "/3",
+                _ => "/",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(format!(r#"<html><body><a href="{next}">Next</a></body></html>"#))
+        })
+        .await?;
+        let client = Client::new();
+
+        for (max_depth, expected) in [(Some(0), 1), (Some(2), 3), (None, 4)] {
+            let options = ProfileOptions {
+                max_depth,
+                ..Default::default()
+            };
+            let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+            assert_eq!(profile.sampled, expected, "max_depth {max_depth:?}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_deny_query_regex() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = if request.path == "/" {
+                let facets: String = (1..=10)
+                    .map(|n| format!(r#"<a href="/list?sort=asc&page={n}">Page {n}</a>"#))
+                    .collect();
+                format!(r#"<html><body>{facets}<a href="/about?ref=nav">About</a></body></html>"#)
+            } else {
+                "<html><body>Leaf</body></html>".to_string()
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let client = Client::new();
+
+        let options = ProfileOptions {
+            deny_query: vec![Regex::new(r"(^|&)page=")?],
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 2);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/about?ref=nav"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_limit_per_status() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            if request.path == "/" {
+                let links: String = (1..=12)
+                    .map(|n| format!(r#"<a href="/missing/{n}">Missing {n}</a>"#))
+                    .collect();
+                MockResponse::new(200)
+                    .header("Content-Type", "text/html")
+                    .body(format!("<html><body>{links}</body></html>"))
+            } else {
+                MockResponse::new(404)
+            }
+        })
+        .await?;
+        let options = ProfileOptions {
+            limit_per_status: Some(5),
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.status_codes[&404], 12);
+        assert_eq!(profile.urls_by_status[&404].len(), 5);
+        assert!(profile.urls_by_status[&404][0].ends_with("/missing/1"));
+        assert_eq!(profile.urls_by_status[&200].len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_seed_robots() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body("User-agent: *\nDisallow: /private/\n"),
+            _ => MockResponse::new(404),
+        })
+        .await?;
+        let client = Client::new();
+
+        let (decision, robot) =
+            SiteProfile::check_seed_robots(&client, &server.url.join("/private/start")?, "Marahuyo", &FetchOptions::default(), None).await;
+        assert!(!decision.allowed, "{decision:?}");
+        assert!(matches!(robot, Some(RobotsFetchResult::Success(_))), "{robot:?}");
+
+        let (decision, _) =
+            SiteProfile::check_seed_robots(&client, &server.url.join("/public/start")?, "Marahuyo", &FetchOptions::default(), None).await;
+        assert!(decision.allowed, "{decision:?}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_skips_robots_disallowed_urls() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body("User-agent: *\nDisallow: /private/\n"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/private/secret">Secret</a><a href="/public">Public</a></body></html>"#),
+        })
+        .await?;
+        let client = Client::new();
+        let (_, robots) = SiteProfile::check_seed_robots(&client, &server.url, "Marahuyo", &FetchOptions::default(), None).await;
+        let options = ProfileOptions {
+            robots: HashMap::from([(server.url.host_str().unwrap_or_default().to_string(), robots.expect("robots.txt"))]),
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 2);
+        assert_eq!(profile.robots_disallowed, 1);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/robots.txt", "/", "/public"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_refuses_forbidden_robots() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| MockResponse::new(200).header("Content-Type", "text/html").body("<html></html>")).await?;
+        let host = server.url.host_str().unwrap_or_default().to_string();
+
+        for robots in [RobotsFetchResult::Forbidden, RobotsFetchResult::Unreachable(503)] {
+            let options = ProfileOptions {
+                robots: HashMap::from([(host.clone(), robots)]),
+                ..Default::default()
+            };
+            let result = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await;
+            assert!(result.is_err(), "{result:?}");
+        }
+        assert!(server.requests().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_honors_user_agent_crawl_delay() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body("User-agent: Marahuyo\nCrawl-delay: 0.2\n\nUser-agent: *\nCrawl-delay: 5\n"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/a">A</a><a href="/b">B</a></body></html>"#),
+        })
+        .await?;
+        let client = Client::new();
+        let (_, robots) = SiteProfile::check_seed_robots(&client, &server.url, "Marahuyo", &FetchOptions::default(), None).await;
+        let Some(RobotsFetchResult::Success(robot)) = robots else {
+            anyhow::bail!("expected a parsed robots.txt, got {robots:?}");
+        };
+        assert_eq!(robot.min_request_interval("Marahuyo"), Some(Duration::from_millis(200)));
+        let options = ProfileOptions {
+            crawl_delays: HashMap::from([(server.url.host_str().unwrap_or_default().to_string(), Duration::from_millis(200))]),
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+        let elapsed = started.elapsed();
+
+        // Three requests are two delays apart; the wildcard group's 5s delay is not used
+        assert_eq!(profile.sampled, 3);
+        assert!(elapsed >= Duration::from_millis(400), "finished in {elapsed:?}");
+        assert!(elapsed < Duration::from_secs(3), "finished in {elapsed:?}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stricter_of_crawl_delay_and_rate_limit_wins() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/a">A</a><a href="/b">B</a></body></html>"#)
+        })
+        .await?;
+        let host = server.url.host_str().unwrap_or_default().to_string();
+        let timed_sample = |crawl_delay: Duration, requests_per_second: u32| {
+            let options = ProfileOptions {
+                crawl_delays: HashMap::from([(host.clone(), crawl_delay)]),
+                fetch: FetchOptions {
+                    rate_limiter: Some(Arc::new(RateLimiter::per_second(requests_per_second))),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let url = server.url.clone();
+            async move {
+                let started = std::time::Instant::now();
+                SiteProfile::sample(&Client::new(), &url, &options, &RetryBudget::default()).await?;
+                anyhow::Ok(started.elapsed())
+            }
+        };
+
+        // Crawl-delay is stricter than 1000 requests/second
+        let elapsed = timed_sample(Duration::from_millis(200), 1000).await?;
+        assert!(elapsed >= Duration::from_millis(400), "finished in {elapsed:?}");
+        // 5 requests/second is stricter than a 10ms crawl-delay
+        let elapsed = timed_sample(Duration::from_millis(10), 5).await?;
+        assert!(elapsed >= Duration::from_millis(400), "finished in {elapsed:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crawl_delay_is_capped() {
+        let options = ProfileOptions {
+            crawl_delays: HashMap::from([
+                ("slow.example".to_string(), Duration::from_secs(86_400)),
+                ("fast.example".to_string(), Duration::from_secs(2)),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(options.crawl_delay_for("slow.example"), Some(Duration::from_secs(60)));
+        assert_eq!(options.crawl_delay_for("fast.example"), Some(Duration::from_secs(2)));
+        assert_eq!(options.crawl_delay_for("other.example"), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_interrupts_crawl_delay() -> anyhow::Result<()> {
+        let server = MockServer::start(|_| {
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/a">A</a></body></html>"#)
+        })
+        .await?;
+        let options = ProfileOptions {
+            crawl_delays: HashMap::from([(server.url.host_str().unwrap_or_default().to_string(), Duration::from_secs(30))]),
+            ..Default::default()
+        };
+        let shutdown = options.shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            shutdown.store(true, Ordering::Relaxed);
+        });
+
+        let started = std::time::Instant::now();
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+        let elapsed = started.elapsed();
+
+        assert!(profile.interrupted);
+        assert_eq!(profile.sampled, 1);
+        assert!(elapsed < Duration::from_secs(3), "finished in {elapsed:?}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_host_robots_reads_each_allowed_host() -> anyhow::Result<()> {
+        // The same server reached as `localhost` stands in for a second domain
+        let server = MockServer::start(|request| {
+            let delay = match request.header("Host").and_then(|host| host.split(':').next()) {
+                Some("localhost") => "3",
+                _ => "1",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/plain")
+                .body(format!("User-agent: *\nCrawl-delay: {delay}\n"))
+        })
+        .await?;
+
+        let robots = SiteProfile::fetch_host_robots(&Client::new(), &server.url, &["localhost".to_string()], &FetchOptions::default()).await;
+
+        let Some(RobotsFetchResult::Success(robot)) = robots.get("localhost") else {
+            anyhow::bail!("expected robots.txt for localhost, got {:?}", robots.keys());
+        };
+        assert_eq!(robot.min_request_interval("Marahuyo"), Some(Duration::from_secs(3)));
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/robots.txt"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_sample_rate_ratio() {
+        let options = ProfileOptions {
+            link_sample_rate: 0.3,
+            fetch: FetchOptions {
+                rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(7))),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let kept = (0..10_000).filter(|_| options.sample_link()).count();
+
+        assert!((2_700..3_300).contains(&kept), "kept {kept} of 10000 links");
+        assert!(ProfileOptions::default().sample_link());
+    }
+
+    #[tokio::test]
+    async fn test_link_sample_rate_never_drops_seeds() -> anyhow::Result<()> {
+        let origin: Arc<OnceLock<String>> = Arc::default();
+        let handler_origin = origin.clone();
+        let server = MockServer::start(move |request| {
+            let origin = handler_origin.get().unwrap();
+            match request.path.as_str() {
+                "/robots.txt" => MockResponse::new(200).body("User-agent: *\nSitemap: /sitemap.xml\n"),
+                "/sitemap.xml" => MockResponse::new(200).body(format!(
+                    "<urlset><url><loc>{origin}/seed-a</loc></url><url><loc>{origin}/seed-b</loc></url></urlset>"
+                )),
+                _ => MockResponse::new(200)
+                    .header("Content-Type", "text/html")
+                    .body(r#"<html><body><a href="/linked-1">1</a><a href="/linked-2">2</a></body></html>"#),
+            }
+        })
+        .await?;
+        origin.set(server.url.as_str().trim_end_matches('/').to_string()).unwrap();
+        let options = ProfileOptions {
+            use_sitemap: true,
+            link_sample_rate: 0.0,
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 3);
+        let pages: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|r| r.path)
+            .filter(|path| !path.ends_with(".txt") && !path.ends_with(".xml"))
+            .collect();
+        assert_eq!(pages, ["/", "/seed-a", "/seed-b"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_covers_robots_and_sitemaps() -> anyhow::Result<()> {
+        let origin: Arc<OnceLock<String>> = Arc::default();
+        let handler_origin = origin.clone();
+        let server = MockServer::start(move |request| {
+            let origin = handler_origin.get().unwrap();
+            match request.path.as_str() {
+                "/robots.txt" => MockResponse::new(200).body("User-agent: *\nSitemap: /sitemap.xml\n"),
+                "/sitemap.xml" => MockResponse::new(200).body(format!("<urlset><url><loc>{origin}/seed</loc></url></urlset>")),
+                _ => MockResponse::new(200)
+                    .header("Content-Type", "text/html")
+                    .body("<html><body>No links</body></html>"),
+            }
+        })
+        .await?;
+        origin.set(server.url.as_str().trim_end_matches('/').to_string()).unwrap();
+        let options = ProfileOptions {
+            use_sitemap: true,
+            fetch: FetchOptions {
+                rate_limiter: Some(Arc::new(RateLimiter::per_second(10))),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+        let elapsed = started.elapsed();
+
+        // robots.txt, the sitemap and both pages all wait for a slot, 100ms apart
+        assert_eq!(server.requests().len(), 4);
+        assert!(elapsed >= Duration::from_millis(300), "finished in {elapsed:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_exclude_precedence() -> anyhow::Result<()> {
+        let options = ProfileOptions {
+            include: vec![Regex::new(r"/blog/")?],
+            exclude: vec![Regex::new(r"/blog/drafts/")?],
+            ..Default::default()
+        };
+
+        assert!(options.should_enqueue(&Url::parse("https://example.com/blog/post")?));
+        assert!(!options.should_enqueue(&Url::parse("https://example.com/blog/drafts/post")?));
+        assert!(!options.should_enqueue(&Url::parse("https://example.com/about")?));
+        assert!(ProfileOptions::default().should_enqueue(&Url::parse("https://example.com/about")?));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_restricted_to_blog() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body>
+                    <a href="/blog/">Blog</a>
+                    <a href="/shop/">Shop</a>
+                    <a href="/about">About</a>
+                </body></html>"#,
+                "/blog/" => r#"<html><body>
+                    <a href="/blog/first">First</a>
+                    <a href="/blog/drafts/wip">Draft</a>
+                    <a href="/contact">Contact</a>
+                </body></html>"#,
+                _ => "<html><body>Leaf</body></html>",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let options = ProfileOptions {
+            include: vec![Regex::new(r"^http://[^/]+/blog/")?],
+            exclude: vec![Regex::new(r"/drafts/")?],
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 3);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/blog/", "/blog/first"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_allow_and_block_lists() -> anyhow::Result<()> {
+        let start = Url::parse("https://example.com/")?;
+        let options = ProfileOptions {
+            allow_domains: vec!["docs.example.com".to_string()],
+            block_domains: vec!["Example.com".to_string()],
+            ..Default::default()
+        };
+
+        assert!(options.is_host_allowed(&Url::parse("https://docs.example.com/guide")?, &start));
+        assert!(!options.is_host_allowed(&Url::parse("https://example.com/about")?, &start));
+        assert!(!options.is_host_allowed(&Url::parse("https://other.com/")?, &start));
+        assert!(!ProfileOptions::default().is_host_allowed(&Url::parse("https://docs.example.com/")?, &start));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_follows_allowed_domain() -> anyhow::Result<()> {
+        // The same server reached as `localhost` stands in for a second domain
+        let server = MockServer::start(|request| {
+            let port = request.header("Host").and_then(|host| host.rsplit(':').next()).unwrap_or_default();
+            let body = match request.path.as_str() {
+                "/" => format!(
+                    r#"<html><body>
+                    <a href="http://localhost:{port}/docs">Docs</a>
+                    <a href="https://blocked.example/">Blocked</a>
+                    <a href="/about">About</a>
+                </body></html>"#
+                ),
+                _ => "<html><body>Leaf</body></html>".to_string(),
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let options = ProfileOptions {
+            allow_domains: vec!["localhost".to_string()],
+            block_domains: vec!["blocked.example".to_string()],
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 3);
+        let hosts: Vec<String> = server
+            .requests()
+            .into_iter()
+            .filter_map(|r| Some(format!("{} {}", r.header("Host")?.split(':').next()?, r.path)))
+            .collect();
+        assert_eq!(hosts, ["127.0.0.1 /", "127.0.0.1 /about", "localhost /docs"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_deduplicates_normalized_urls() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/" => r##"<html><body>
+                    <a href="/docs/">Docs</a>
+                    <a href="/docs#install">Install</a>
+                    <a href="/docs/#usage">Usage</a>
+                </body></html>"##,
+                _ => "<html><body>Leaf</body></html>",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 3);
+
+        let options = ProfileOptions {
+            strip_trailing_slash: true,
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_skips_canonical_duplicates() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let host = request.header("Host").unwrap_or_default();
+            let canonical = |path: &str| format!(r#"<link rel="canonical" href="http://{host}{path}">"#);
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body>
+                    <a href="/list">List</a>
+                    <a href="/list?sort=asc">Sorted</a>
+                    <a href="/page?ref=home">Tracked</a>
+                </body></html>"#
+                    .to_string(),
+                "/list?sort=asc" => format!(
+                    r#"<html><head>{}</head><body><a href="/list?sort=asc&amp;page=2">Next</a></body></html>"#,
+                    canonical("/list")
+                ),
+                "/page?ref=home" => format!(
+                    r#"<html><head>{}</head><body><a href="/page?ref=self">Self</a></body></html>"#,
+                    canonical("/page")
+                ),
+                "/page?ref=self" => {
+                    r#"<html><head><link rel="canonical" href="https://evil.example/"></head><body>Leaf</body></html>"#
+                        .to_string()
+                }
+                _ => format!("<html><head>{}</head><body>Leaf</body></html>", canonical("/list")),
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/list", "/list?sort=asc", "/page?ref=home", "/page?ref=self"]);
+        assert_eq!(
+            profile.canonical_urls.get(server.url.join("/page?ref=home")?.as_str()),
+            Some(&server.url.join("/page")?.to_string())
+        );
+        assert_eq!(profile.canonical_urls.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_does_not_refetch_redirect_target() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/old">Old</a></body></html>"#),
+            "/old" => MockResponse::new(301).header("Location", "/new"),
+            _ => MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/new">New</a><a href="/other">Other</a></body></html>"#),
+        })
+        .await?;
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 3);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/old", "/new", "/other"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_stops_on_shutdown() -> anyhow::Result<()> {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let requested = shutdown.clone();
+        let server = MockServer::start(move |request| {
+            // Requested while /a is in flight; /a still completes
+            if request.path == "/a" {
+                requested.store(true, Ordering::Relaxed);
+            }
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(r#"<html><body><a href="/a">A</a><a href="/b">B</a></body></html>"#)
+        })
+        .await?;
+        let options = ProfileOptions {
+            shutdown,
+            ..Default::default()
+        };
+
+        let profile = SiteProfile::sample(&Client::new(), &server.url, &options, &RetryBudget::default()).await?;
+
+        assert!(profile.interrupted);
+        assert_eq!(profile.sampled, 2);
+        assert_eq!(profile.status_codes.get(&200), Some(&2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_skip_adult() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body><a href="/adult">Adult</a></body></html>"#,
+                "/adult" => r#"<html><head><meta name="rating" content="adult"></head>
+                    <body><a href="/behind-adult">More</a></body></html>"#,
+                _ => "<html><body>Leaf</body></html>",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let client = Client::new();
+
+        let profile = SiteProfile::sample(&client, &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 3);
+
+        let options = ProfileOptions {
+            skip_adult: true,
+            ..Default::default()
+        };
+        let profile = SiteProfile::sample(&client, &server.url, &options, &RetryBudget::default()).await?;
+        assert_eq!(profile.sampled, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_profile_honors_meta_nofollow() -> anyhow::Result<()> {
+        let server = MockServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/" => r#"<html><body>
+                    <a href="/nofollow">Next</a>
+                    <a href="/sponsored" rel="nofollow noopener">Sponsored</a>
+                </body></html>"#,
+                "/nofollow" => r#"<html><head><meta name="robots" content="noindex, NOFOLLOW"></head>
+                    <body><a href="/hidden">Hidden</a></body></html>"#,
+                _ => "<html><body>Leaf</body></html>",
+            };
+            MockResponse::new(200)
+                .header("Content-Type", "text/html")
+                .body(body)
+        })
+        .await?;
+        let client = Client::new();
+
+        let profile = SiteProfile::sample(&client, &server.url, &ProfileOptions::default(), &RetryBudget::default()).await?;
+
+        assert_eq!(profile.sampled, 2);
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, ["/", "/nofollow"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use scraper::{Html, Selector};
+use serde::Serialize;
+use tracing::debug;
+use url::Url;
+
+use crate::extract_metadata::PageMetadata;
+
+/// Recommended title length bounds, in characters
+const TITLE_LENGTH: (usize, usize) = (30, 60);
+/// Recommended meta description length bounds, in characters
+const DESCRIPTION_LENGTH: (usize, usize) = (70, 160);
+
+/// A single SEO problem found on a page
+#[derive(Debug, Clone, Serialize)]
+pub struct SeoIssue {
+    /// Stable machine-readable identifier (e.g. `missing_title`)
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// SEO audit of a single page, built on top of the metadata extractors
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeoReport {
+    pub issues: Vec<SeoIssue>,
+}
+
+impl SeoReport {
+    /// Audits `document`, served from `url`, for common SEO problems
+    pub fn audit(url: &Url, document: &Html) -> anyhow::Result<Self> {
+        let metadata = PageMetadata::extract(document)?;
+        let mut report = SeoReport::default();
+
+        // Inline SVG icons carry their own <title> tooltips, so only the document title counts
+        let titles = Self::texts(document, "head > title");
+        match titles.first().map(|title| title.trim()) {
+            None | Some("") => report.push("missing_title", "Page has no <title>".to_string()),
+            Some(title) => Self::check_length(&mut report, "title", title, TITLE_LENGTH),
+        }
+        if titles.len() > 1 {
+            report.push(
+                "duplicate_title",
+                format!("Page has {} <title> elements", titles.len()),
+            );
+        }
+
+        match metadata.basic.description.as_deref().map(str::trim) {
+            None | Some("") => report.push(
+                "missing_description",
+                "Page has no meta description".to_string(),
+            ),
+            Some(description) => {
+                Self::check_length(&mut report, "description", description, DESCRIPTION_LENGTH)
+            }
+        }
+
+        let noindex = !metadata.robots_directives().index;
+        if let Some(canonical) = &metadata.seo.canonical {
+            if noindex {
+                report.push(
+                    "noindex_with_canonical",
+                    format!(
+                        "Page is noindex but declares canonical {}; search engines get conflicting signals",
+                        canonical
+                    ),
+                );
+            } else if !Self::same_page(canonical, url) {
+                report.push(
+                    "canonical_elsewhere",
+                    format!(
+                        "Page is indexable but its canonical points elsewhere: {}",
+                        canonical
+                    ),
+                );
+            }
+        }
+
+        let h1_count = Self::texts(document, "h1").len();
+        match h1_count {
+            0 => report.push("missing_h1", "Page has no <h1>".to_string()),
+            1 => {}
+            count => report.push("multiple_h1", format!("Page has {} <h1> elements", count)),
+        }
+
+        let selector = Selector::parse("img").map_err(|e| anyhow::anyhow!("{e}"))?;
+        for image in document.select(&selector) {
+            if image.value().attr("alt").is_none() {
+                let src = image.value().attr("src").unwrap_or("(no src)");
+                report.push("image_missing_alt", format!("Image {} has no alt attribute", src));
+            }
+        }
+
+        debug!(url = %url, issues = report.issues.len(), "SEO audit complete");
+        Ok(report)
+    }
+
+    fn push(&mut self, code: &'static str, message: String) {
+        self.issues.push(SeoIssue { code, message });
+    }
+
+    /// Flags `value` when its length in characters falls outside `(min, max)`
+    fn check_length(&mut self, field: &str, value: &str, (min, max): (usize, usize)) {
+        let length = value.chars().count();
+        if length < min {
+            self.push(
+                if field == "title" { "title_too_short" } else { "description_too_short" },
+                format!("The {} is {} characters, below the recommended {}", field, length, min),
+            );
+        } else if length > max {
+            self.push(
+                if field == "title" { "title_too_long" } else { "description_too_long" },
+                format!("The {} is {} characters, above the recommended {}", field, length, max),
+            );
+        }
+    }
+
+    fn texts(document: &Html, selector: &str) -> Vec<String> {
+        Selector::parse(selector)
+            .map(|selector| {
+                document
+                    .select(&selector)
+                    .map(|el| el.text().collect::<String>())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Compares two URLs ignoring the fragment
+    fn same_page(a: &Url, b: &Url) -> bool {
+        let mut a = a.clone();
+        let mut b = b.clone();
+        a.set_fragment(None);
+        b.set_fragment(None);
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use url::Url;
+
+    use crate::seo_report::SeoReport;
+
+    #[test]
+    fn test_seo_issues_fixture() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(include_str!("../test-site/pages/seo-issues.html"));
+        let url = Url::parse("https://example.com/pages/seo-issues.html")?;
+
+        let report = SeoReport::audit(&url, &document)?;
+        let codes: Vec<&str> = report.issues.iter().map(|issue| issue.code).collect();
+
+        assert_eq!(
+            codes,
+            [
+                "title_too_short",
+                "duplicate_title",
+                "missing_description",
+                "canonical_elsewhere",
+                "multiple_h1",
+                "image_missing_alt",
+            ]
+        );
+        assert!(report.issues[5].message.contains("/images/no-alt.png"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_noindex_canonical_and_missing_elements() -> anyhow::Result<()> {
+        let url = Url::parse("https://example.com/copy")?;
+
+        // `none` is shorthand for `noindex, nofollow`
+        for robots in ["NOINDEX, follow", "none"] {
+            let document = scraper::Html::parse_document(&format!(
+                r#"<html><head>
+                    <meta name="robots" content="{robots}">
+                    <link rel="canonical" href="https://example.com/original">
+                </head><body>
+                    <svg><title>Search icon tooltip that is long enough to pass the length check</title></svg>
+                    <p>No heading here</p>
+                </body></html>"#
+            ));
+
+            let report = SeoReport::audit(&url, &document)?;
+            let codes: Vec<&str> = report.issues.iter().map(|issue| issue.code).collect();
+
+            assert_eq!(
+                codes,
+                ["missing_title", "missing_description", "noindex_with_canonical", "missing_h1"],
+                "robots: {robots}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_page_has_no_issues() -> anyhow::Result<()> {
+        let document = scraper::Html::parse_document(
+            r#"<html><head>
+                <title>A well sized page title for the audit test</title>
+                <meta name="description" content="A meta description that is long enough to sit comfortably inside the recommended bounds.">
+                <link rel="canonical" href="https://example.com/page">
+            </head><body>
+                <h1>Heading</h1><img src="/a.png" alt="">
+                <svg viewBox="0 0 16 16"><title>Menu</title><path d="M0 0h16v2H0z"/></svg>
+            </body></html>"#,
+        );
+        let url = Url::parse("https://example.com/page#top")?;
+
+        assert!(SeoReport::audit(&url, &document)?.issues.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,871 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::Read;
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, Utc};
+use quick_xml::events::Event;
+use reqwest::{Client, Method};
+use serde::Serialize;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::check_robots::{RobotsFetchResult, fetch_robots, fetch_robots_from};
+use crate::fetch::{RateLimiter, send_following_redirects};
+
+/// Maximum number of URLs allowed in a single sitemap per the sitemaps.org protocol
+const MAX_SITEMAP_URLS: usize = 50_000;
+/// Maximum uncompressed sitemap size per the sitemaps.org protocol
+const MAX_SITEMAP_SIZE: usize = 50 * 1024 * 1024; // 50 MiB
+/// Maximum number of entries collected when flattening a sitemap index
+const MAX_FLATTENED_URLS: usize = 10 * MAX_SITEMAP_URLS;
+
+/// Valid `<changefreq>` values per the sitemaps.org protocol
+const CHANGEFREQ_VALUES: [&str; 7] = [
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+/// A `<url>` (or `<sitemap>`) entry with its fields exactly as written in the document
+#[derive(Debug, Clone, Default)]
+pub struct RawSiteMapEntry {
+    pub loc: Option<String>,
+    pub lastmod: Option<String>,
+    pub changefreq: Option<String>,
+    pub priority: Option<String>,
+}
+
+/// A sitemap document parsed without interpreting its values
+/// Used for validation, where malformed values must be reported rather than dropped
+#[derive(Debug, Clone, Default)]
+pub struct RawSiteMap {
+    /// Local name of the root element (`urlset` or `sitemapindex`)
+    pub root: String,
+    pub entries: Vec<RawSiteMapEntry>,
+}
+
+impl RawSiteMap {
+    /// Parses the `<url>`/`<sitemap>` entries of a sitemap document
+    /// Fails on malformed XML; elements from extension namespaces (e.g. `image:loc`) are ignored
+    pub fn parse(xml: &str) -> anyhow::Result<Self> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut sitemap = RawSiteMap::default();
+        let mut current_entry: Option<RawSiteMapEntry> = None;
+        let mut current_field: Option<String> = None;
+        let mut text = String::new();
+        let mut depth = 0;
+
+        loop {
+            let event = reader.read_event().map_err(|e| {
+                anyhow!(
+                    "Malformed sitemap XML at position {}: {}",
+                    reader.error_position(),
+                    e
+                )
+            })?;
+
+            match event {
+                Event::Start(e) => {
+                    depth += 1;
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    match depth {
+                        1 => sitemap.root = name,
+                        2 if name == "url" || name == "sitemap" => {
+                            current_entry = Some(RawSiteMapEntry::default());
+                        }
+                        3 if current_entry.is_some() => {
+                            current_field = Some(name);
+                            text.clear();
+                        }
+                        _ => {}
+                    }
+                }
+                Event::End(_) => {
+                    match depth {
+                        2 => {
+                            if let Some(entry) = current_entry.take() {
+                                sitemap.entries.push(entry);
+                            }
+                        }
+                        3 => {
+                            if let (Some(entry), Some(field)) =
+                                (current_entry.as_mut(), current_field.take())
+                            {
+                                let value = Some(text.trim().to_string());
+                                match field.as_str() {
+                                    "loc" => entry.loc = value,
+                                    "lastmod" => entry.lastmod = value,
+                                    "changefreq" => entry.changefreq = value,
+                                    "priority" => entry.priority = value,
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    depth -= 1;
+                }
+                Event::Empty(e) if depth == 0 => {
+                    sitemap.root = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                }
+                Event::Text(e) if current_field.is_some() => {
+                    text.push_str(&e.xml_content()?);
+                }
+                Event::CData(e) if current_field.is_some() => {
+                    text.push_str(&e.decode()?);
+                }
+                Event::GeneralRef(e) if current_field.is_some() => {
+                    if let Some(c) = e.resolve_char_ref()? {
+                        text.push(c);
+                    } else {
+                        let entity = e.decode()?;
+                        let resolved = quick_xml::escape::resolve_predefined_entity(&entity)
+                            .ok_or_else(|| anyhow!("Unknown entity in sitemap: &{};", entity))?;
+                        text.push_str(resolved);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        if sitemap.root.is_empty() {
+            return Err(anyhow!("Malformed sitemap XML: no root element"));
+        }
+
+        debug!(
+            root = %sitemap.root,
+            entries = sitemap.entries.len(),
+            "Parsed sitemap document"
+        );
+        Ok(sitemap)
+    }
+}
+
+/// A `<url>` entry with its values interpreted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteMapEntry {
+    pub loc: Url,
+    pub lastmod: Option<DateTime<Utc>>,
+    pub changefreq: Option<String>,
+    pub priority: Option<f32>,
+}
+
+/// A `<urlset>` sitemap document
+#[derive(Debug, Clone, Default)]
+pub struct SiteMap {
+    pub urls: Vec<SiteMapEntry>,
+}
+
+impl SiteMap {
+    /// Parses a `<urlset>` sitemap document
+    /// Fails on malformed XML or another root element; entries without a valid absolute `<loc>` are skipped
+    /// and unparseable optional fields are dropped, matching the lenient robots.txt parser
+    #[allow(dead_code)]
+    pub fn parse(xml: &str) -> anyhow::Result<Self> {
+        match SiteMapDocument::parse(xml)? {
+            SiteMapDocument::UrlSet(sitemap) => Ok(sitemap),
+            SiteMapDocument::Index(_) => Err(anyhow!(
+                "Expected a <urlset> sitemap, found <sitemapindex>"
+            )),
+        }
+    }
+
+    /// Fetches the sitemap at `url`, following sitemap indexes up to `max_depth` levels,
+    /// and flattens every `<url>` entry into a single list
+    /// Sitemaps already visited are skipped, child sitemaps that fail to load are logged and skipped,
+    /// and collection stops once the flattened list reaches its size cap
+    pub async fn fetch_recursive(
+        client: &Client,
+        url: &Url,
+        max_depth: usize,
+        rate_limiter: Option<&RateLimiter>,
+    ) -> anyhow::Result<Vec<SiteMapEntry>> {
+        let mut entries = Vec::new();
+        let mut visited = HashSet::from([url.to_string()]);
+        let mut pending = VecDeque::from([(url.clone(), 0)]);
+
+        while let Some((sitemap_url, depth)) = pending.pop_front() {
+            let document = match fetch_sitemap(client, &sitemap_url, rate_limiter)
+                .await
+                .and_then(|xml| SiteMapDocument::parse(&xml))
+            {
+                Ok(document) => document,
+                // The sitemap that was asked for must load; children are best-effort
+                Err(e) if depth == 0 => return Err(e),
+                Err(e) => {
+                    warn!(url = %sitemap_url, error = %e, "Skipping child sitemap");
+                    continue;
+                }
+            };
+
+            match document {
+                SiteMapDocument::UrlSet(sitemap) => {
+                    let room = MAX_FLATTENED_URLS - entries.len();
+                    if sitemap.urls.len() > room {
+                        warn!(
+                            "Sitemap entry limit of {} reached, ignoring the remaining entries",
+                            MAX_FLATTENED_URLS
+                        );
+                        entries.extend(sitemap.urls.into_iter().take(room));
+                        break;
+                    }
+                    entries.extend(sitemap.urls);
+                }
+                SiteMapDocument::Index(index) if depth < max_depth => {
+                    for child in index.sitemaps {
+                        if visited.insert(child.loc.to_string()) {
+                            pending.push_back((child.loc, depth + 1));
+                        } else {
+                            debug!(url = %child.loc, "Skipping already visited sitemap");
+                        }
+                    }
+                }
+                SiteMapDocument::Index(_) => {
+                    warn!(url = %sitemap_url, max_depth, "Not following sitemap index beyond max depth");
+                }
+            }
+        }
+
+        debug!(entries = entries.len(), "Fetched sitemap recursively");
+        Ok(entries)
+    }
+
+    fn from_raw(raw: RawSiteMap) -> Self {
+        let mut urls = Vec::with_capacity(raw.entries.len());
+        for entry in raw.entries {
+            let Some(loc) = entry.loc.as_deref().and_then(|loc| Url::parse(loc).ok()) else {
+                warn!("Skipping sitemap entry with invalid <loc>: {:?}", entry.loc);
+                continue;
+            };
+
+            urls.push(SiteMapEntry {
+                loc,
+                lastmod: entry.lastmod.as_deref().and_then(parse_w3c_datetime),
+                changefreq: entry
+                    .changefreq
+                    .map(|c| c.to_lowercase())
+                    .filter(|c| CHANGEFREQ_VALUES.contains(&c.as_str())),
+                priority: entry
+                    .priority
+                    .and_then(|p| p.parse::<f32>().ok())
+                    .filter(|p| (0.0..=1.0).contains(p)),
+            });
+        }
+
+        debug!(urls = urls.len(), "Parsed typed sitemap");
+        SiteMap { urls }
+    }
+}
+
+/// A `<sitemap>` entry of a sitemap index
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteMapIndexEntry {
+    pub loc: Url,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+/// A `<sitemapindex>` document listing child sitemaps
+#[derive(Debug, Clone, Default)]
+pub struct SiteMapIndex {
+    pub sitemaps: Vec<SiteMapIndexEntry>,
+}
+
+#[allow(dead_code)]
+impl SiteMapIndex {
+    /// Parses a `<sitemapindex>` document
+    /// Fails on malformed XML or another root element; entries without a valid absolute `<loc>` are skipped
+    pub fn parse(xml: &str) -> anyhow::Result<Self> {
+        match SiteMapDocument::parse(xml)? {
+            SiteMapDocument::Index(index) => Ok(index),
+            SiteMapDocument::UrlSet(_) => Err(anyhow!(
+                "Expected a <sitemapindex>, found <urlset>"
+            )),
+        }
+    }
+}
+
+/// Either kind of sitemap document, told apart by its root element
+#[derive(Debug, Clone)]
+pub enum SiteMapDocument {
+    UrlSet(SiteMap),
+    Index(SiteMapIndex),
+}
+
+impl SiteMapDocument {
+    /// Parses a `<urlset>` or `<sitemapindex>` document
+    pub fn parse(xml: &str) -> anyhow::Result<Self> {
+        let raw = RawSiteMap::parse(xml)?;
+        match raw.root.as_str() {
+            "urlset" => Ok(SiteMapDocument::UrlSet(SiteMap::from_raw(raw))),
+            "sitemapindex" => {
+                let sitemaps = raw
+                    .entries
+                    .into_iter()
+                    .filter_map(|entry| {
+                        let Some(loc) = entry.loc.as_deref().and_then(|loc| Url::parse(loc).ok())
+                        else {
+                            warn!("Skipping sitemap index entry with invalid <loc>: {:?}", entry.loc);
+                            return None;
+                        };
+                        Some(SiteMapIndexEntry {
+                            loc,
+                            lastmod: entry.lastmod.as_deref().and_then(parse_w3c_datetime),
+                        })
+                    })
+                    .collect();
+                Ok(SiteMapDocument::Index(SiteMapIndex { sitemaps }))
+            }
+            root => Err(anyhow!(
+                "Expected a <urlset> or <sitemapindex> sitemap, found <{}>",
+                root
+            )),
+        }
+    }
+}
+
+/// A single sitemaps.org protocol violation
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteMapViolation {
+    /// 1-based position of the offending entry, `None` for document-level problems
+    pub entry: Option<usize>,
+    /// The offending entry's `<loc>`, when present
+    pub loc: Option<String>,
+    pub message: String,
+}
+
+/// Result of linting a sitemap against the sitemaps.org protocol
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteMapReport {
+    pub entries: usize,
+    pub violations: Vec<SiteMapViolation>,
+}
+
+impl SiteMapReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Fetches a sitemap document as text
+/// Gzipped sitemaps (a `.gz` URL, a gzip content type or `Content-Encoding: gzip`) are decompressed
+pub async fn fetch_sitemap(client: &Client, url: &Url, rate_limiter: Option<&RateLimiter>) -> anyhow::Result<String> {
+    debug!("Fetching sitemap from: {}", url);
+    if let Some(rate_limiter) = rate_limiter {
+        rate_limiter.acquire().await;
+    }
+    let response = send_following_redirects(client, Method::GET, url).await?.error_for_status()?;
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_lowercase())
+            .unwrap_or_default()
+    };
+    let gzipped = url.path().ends_with(".gz")
+        || header("Content-Encoding").contains("gzip")
+        || header("Content-Type").contains("gzip");
+
+    let body = response.bytes().await?;
+    // Servers sometimes transparently decompress `.gz` sitemaps, so trust the magic bytes
+    let text = if gzipped && body.starts_with(&[0x1f, 0x8b]) {
+        let text = decompress_sitemap(&body)
+            .map_err(|e| anyhow!("Failed to decompress gzipped sitemap {}: {}", url, e))?;
+        if !text.trim_start_matches('\u{feff}').trim_start().starts_with('<') {
+            return Err(anyhow!(
+                "Decompressed sitemap {} is not an XML document",
+                url
+            ));
+        }
+        debug!("Decompressed gzipped sitemap ({} -> {} bytes)", body.len(), text.len());
+        text
+    } else {
+        String::from_utf8_lossy(&body).into_owned()
+    };
+
+    debug!("Fetched sitemap (size: {} bytes)", text.len());
+    Ok(text)
+}
+
+/// Inflates a gzipped sitemap, reading at most one byte past the 50 MiB limit
+/// so oversized payloads are still reported by validation without being fully expanded
+fn decompress_sitemap(body: &[u8]) -> anyhow::Result<String> {
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(body)
+        .take(MAX_SITEMAP_SIZE as u64 + 1)
+        .read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Sitemaps advertised for a host
+#[derive(Debug, Clone, Default)]
+pub struct SiteMapDiscovery {
+    /// `Sitemap:` entries from robots.txt as written, or the fallback URL when it exists
+    pub sitemaps: Vec<String>,
+    /// The `/sitemap.xml` URL that was checked because robots.txt was missing, and whether it exists
+    pub fallback: Option<(String, bool)>,
+}
+
+/// Lists the sitemaps of the host serving `url` from its robots.txt, or from `robots_url` when given
+/// When robots.txt is missing, falls back to checking whether `/sitemap.xml` exists
+pub async fn discover_sitemaps(
+    client: &Client,
+    url: &Url,
+    robots_url: Option<&Url>,
+    rate_limiter: Option<&RateLimiter>,
+) -> anyhow::Result<SiteMapDiscovery> {
+    let robots = match robots_url {
+        Some(robots_url) => fetch_robots_from(client, robots_url.as_str(), rate_limiter).await?,
+        None => fetch_robots(client, url, rate_limiter).await?,
+    };
+
+    match robots {
+        RobotsFetchResult::Success(robot) => Ok(SiteMapDiscovery {
+            sitemaps: robot.sitemaps(),
+            fallback: None,
+        }),
+        RobotsFetchResult::Forbidden | RobotsFetchResult::Unreachable(_) => Ok(SiteMapDiscovery::default()),
+        RobotsFetchResult::NotFound => {
+            let fallback_url = url.join("/sitemap.xml")?;
+            debug!("robots.txt missing, checking {}", fallback_url);
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            let exists = send_following_redirects(client, Method::GET, &fallback_url)
+                .await
+                .is_ok_and(|response| response.status().is_success());
+
+            Ok(SiteMapDiscovery {
+                sitemaps: if exists { vec![fallback_url.to_string()] } else { Vec::new() },
+                fallback: Some((fallback_url.to_string(), exists)),
+            })
+        }
+    }
+}
+
+/// Validates a sitemap against the sitemaps.org protocol
+/// Every violation is reported, rather than stopping at the first one
+pub fn validate_sitemap(sitemap_url: &Url, xml: &str) -> SiteMapReport {
+    let mut violations = Vec::new();
+    let document_violation = |message: String| SiteMapViolation {
+        entry: None,
+        loc: None,
+        message,
+    };
+
+    if xml.len() > MAX_SITEMAP_SIZE {
+        violations.push(document_violation(format!(
+            "Sitemap is {} bytes, exceeding the 50 MiB limit",
+            xml.len()
+        )));
+    }
+
+    let sitemap = match RawSiteMap::parse(xml) {
+        Ok(sitemap) => sitemap,
+        Err(e) => {
+            warn!("Sitemap is not well-formed: {}", e);
+            violations.push(document_violation(e.to_string()));
+            return SiteMapReport {
+                entries: 0,
+                violations,
+            };
+        }
+    };
+
+    if sitemap.root != "urlset" && sitemap.root != "sitemapindex" {
+        violations.push(document_violation(format!(
+            "Unexpected root element <{}>, expected <urlset> or <sitemapindex>",
+            sitemap.root
+        )));
+    }
+
+    if sitemap.entries.len() > MAX_SITEMAP_URLS {
+        violations.push(document_violation(format!(
+            "Sitemap lists {} URLs, exceeding the 50,000 URL limit",
+            sitemap.entries.len()
+        )));
+    }
+
+    for (idx, entry) in sitemap.entries.iter().enumerate() {
+        let mut violation = |message: String| {
+            violations.push(SiteMapViolation {
+                entry: Some(idx + 1),
+                loc: entry.loc.clone(),
+                message,
+            })
+        };
+
+        match entry.loc.as_deref() {
+            None | Some("") => violation("Missing <loc>".to_string()),
+            Some(loc) => match Url::parse(loc) {
+                Ok(url) if url.scheme() != "http" && url.scheme() != "https" => {
+                    violation(format!("<loc> has unsupported scheme '{}'", url.scheme()))
+                }
+                Ok(url) if url.host_str() != sitemap_url.host_str() => violation(format!(
+                    "<loc> host '{}' differs from the sitemap host '{}'",
+                    url.host_str().unwrap_or(""),
+                    sitemap_url.host_str().unwrap_or("")
+                )),
+                Ok(_) => {}
+                Err(e) => violation(format!("<loc> is not an absolute URL: {}", e)),
+            },
+        }
+
+        if let Some(lastmod) = &entry.lastmod
+            && !is_w3c_datetime(lastmod)
+        {
+            violation(format!("<lastmod> '{}' is not a valid W3C datetime", lastmod));
+        }
+
+        if let Some(changefreq) = &entry.changefreq
+            && !CHANGEFREQ_VALUES.contains(&changefreq.as_str())
+        {
+            violation(format!(
+                "<changefreq> '{}' is not one of {}",
+                changefreq,
+                CHANGEFREQ_VALUES.join(", ")
+            ));
+        }
+
+        if let Some(priority) = &entry.priority {
+            match priority.parse::<f32>() {
+                Ok(p) if (0.0..=1.0).contains(&p) => {}
+                _ => violation(format!("<priority> '{}' is not between 0.0 and 1.0", priority)),
+            }
+        }
+    }
+
+    debug!(
+        entries = sitemap.entries.len(),
+        violations = violations.len(),
+        "Sitemap validation complete"
+    );
+
+    SiteMapReport {
+        entries: sitemap.entries.len(),
+        violations,
+    }
+}
+
+/// Checks a value against the W3C datetime profile used by sitemaps
+/// (`YYYY`, `YYYY-MM`, `YYYY-MM-DD`, or a full date and time with a timezone designator)
+fn is_w3c_datetime(value: &str) -> bool {
+    parse_w3c_datetime(value).is_some()
+}
+
+/// Parses a W3C Datetime value (year, year-month, date, or date-time with timezone) as UTC
+/// Truncated values resolve to the start of the period they name
+fn parse_w3c_datetime(value: &str) -> Option<DateTime<Utc>> {
+    let is_year = |y: &str| y.len() == 4 && y.chars().all(|c| c.is_ascii_digit());
+
+    let date = match value.len() {
+        4 if is_year(value) => NaiveDate::parse_from_str(&format!("{}-01-01", value), "%Y-%m-%d").ok(),
+        4 => None,
+        7 => NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d").ok(),
+        10 => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+        _ => {
+            // hh:mm without seconds is allowed by the W3C profile but not by RFC 3339
+            let normalized = value.replace('Z', "+00:00");
+            return DateTime::parse_from_rfc3339(value)
+                .or_else(|_| DateTime::parse_from_str(&normalized, "%Y-%m-%dT%H:%M%:z"))
+                .ok()
+                .map(|datetime| datetime.with_timezone(&Utc));
+        }
+    };
+
+    date.and_then(|d| d.and_hms_opt(0, 0, 0)).map(|d| d.and_utc())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, OnceLock};
+
+    use reqwest::Client;
+    use url::Url;
+
+    use crate::sitemap::{
+        RawSiteMap, SiteMap, SiteMapDocument, SiteMapEntry, SiteMapIndex, discover_sitemaps,
+        fetch_sitemap, validate_sitemap,
+    };
+    use crate::utils::{MockResponse, MockServer};
+
+    #[test]
+    fn test_raw_sitemap_fixture() -> anyhow::Result<()> {
+        let sitemap = RawSiteMap::parse(include_str!("../test-site/sitemap.xml"))?;
+
+        assert_eq!(sitemap.root, "urlset");
+        assert_eq!(sitemap.entries.len(), 8);
+        assert_eq!(sitemap.entries[0].loc.as_deref(), Some("https://example.com/"));
+        assert_eq!(sitemap.entries[0].priority.as_deref(), Some("1.0"));
+        // <image:loc> belongs to the image extension and must not replace the page <loc>
+        assert_eq!(
+            sitemap.entries[3].loc.as_deref(),
+            Some("https://example.com/pages/metadata.html")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_sitemap_reports_every_violation() -> anyhow::Result<()> {
+        let sitemap_url = Url::parse("https://example.com/sitemap.xml")?;
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <url><loc>https://example.com/ok?a=1&amp;b=2</loc><lastmod>2025-12-08T10:30+01:00</lastmod></url>
+                <url><lastmod>2025-12-08</lastmod></url>
+                <url><loc>/relative.html</loc></url>
+                <url><loc>https://other.example.org/page</loc></url>
+                <url><loc>https://example.com/priority</loc><priority>1.5</priority></url>
+                <url><loc>https://example.com/changefreq</loc><changefreq>sometimes</changefreq></url>
+                <url><loc>https://example.com/lastmod</loc><lastmod>2025-13-45</lastmod></url>
+            </urlset>"#;
+
+        let report = validate_sitemap(&sitemap_url, xml);
+        let violations: Vec<(Option<usize>, &str)> = report
+            .violations
+            .iter()
+            .map(|v| (v.entry, v.message.as_str()))
+            .collect();
+
+        assert_eq!(report.entries, 7);
+        assert_eq!(violations.len(), 6, "{:?}", violations);
+        assert!(violations[0].0 == Some(2) && violations[0].1.contains("Missing <loc>"));
+        assert!(violations[1].0 == Some(3) && violations[1].1.contains("not an absolute URL"));
+        assert!(violations[2].0 == Some(4) && violations[2].1.contains("differs from the sitemap host"));
+        assert!(violations[3].0 == Some(5) && violations[3].1.contains("<priority>"));
+        assert!(violations[4].0 == Some(6) && violations[4].1.contains("<changefreq>"));
+        assert!(violations[5].0 == Some(7) && violations[5].1.contains("<lastmod>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_sitemap_malformed_and_limits() -> anyhow::Result<()> {
+        let sitemap_url = Url::parse("https://example.com/sitemap.xml")?;
+
+        let malformed = validate_sitemap(&sitemap_url, "<urlset><url><loc>x</url></urlset>");
+        assert!(!malformed.is_valid());
+        assert!(malformed.violations[0].message.contains("Malformed sitemap XML"));
+
+        let mut too_many = String::from("<urlset>");
+        for i in 0..50_001 {
+            too_many.push_str(&format!("<url><loc>https://example.com/{}</loc></url>", i));
+        }
+        too_many.push_str("</urlset>");
+        let report = validate_sitemap(&sitemap_url, &too_many);
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].message.contains("50,000 URL limit"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_sitemap_fixture() -> anyhow::Result<()> {
+        let sitemap = SiteMap::parse(include_str!("../test-site/sitemap.xml"))?;
+
+        assert_eq!(sitemap.urls.len(), 8);
+        let first = &sitemap.urls[0];
+        assert_eq!(first.loc.as_str(), "https://example.com/");
+        assert_eq!(
+            first.lastmod.map(|d| d.to_rfc3339()),
+            Some("2025-12-08T00:00:00+00:00".to_string())
+        );
+        assert_eq!(first.changefreq.as_deref(), Some("weekly"));
+        assert_eq!(first.priority, Some(1.0));
+        assert_eq!(
+            sitemap.urls[3].loc.as_str(),
+            "https://example.com/pages/metadata.html"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_sitemap_lenient_fields() -> anyhow::Result<()> {
+        let sitemap = SiteMap::parse(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <url><loc>https://example.com/a</loc><lastmod>2024-05-01T10:30+02:00</lastmod><changefreq>Daily</changefreq><priority>0.5</priority></url>
+                <url><loc>not a url</loc></url>
+                <url><loc>https://example.com/b</loc><lastmod>yesterday</lastmod><changefreq>sometimes</changefreq><priority>2</priority></url>
+            </urlset>"#,
+        )?;
+
+        assert_eq!(sitemap.urls.len(), 2);
+        assert_eq!(
+            sitemap.urls[0].lastmod.map(|d| d.to_rfc3339()),
+            Some("2024-05-01T08:30:00+00:00".to_string())
+        );
+        assert_eq!(sitemap.urls[0].changefreq.as_deref(), Some("daily"));
+        assert_eq!(sitemap.urls[0].priority, Some(0.5));
+        assert_eq!(sitemap.urls[1].loc.as_str(), "https://example.com/b");
+        assert_eq!(sitemap.urls[1].lastmod, None);
+        assert_eq!(sitemap.urls[1].changefreq, None);
+        assert_eq!(sitemap.urls[1].priority, None);
+
+        let error = SiteMap::parse("<urlset><url><loc>https://example.com/</url></urlset>").unwrap_err();
+        assert!(error.to_string().contains("Malformed sitemap XML"));
+        let error = SiteMap::parse("<sitemapindex></sitemapindex>").unwrap_err();
+        assert!(error.to_string().contains("Expected a <urlset>"));
+        let error = SiteMap::parse("<feed></feed>").unwrap_err();
+        assert!(error.to_string().contains("found <feed>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sitemap_index() -> anyhow::Result<()> {
+        let xml = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <sitemap><loc>https://example.com/sitemap-pages.xml</loc><lastmod>2025-01-02</lastmod></sitemap>
+            <sitemap><loc>https://example.com/sitemap-posts.xml.gz</loc></sitemap>
+        </sitemapindex>"#;
+
+        let index = SiteMapIndex::parse(xml)?;
+        assert_eq!(index.sitemaps.len(), 2);
+        assert_eq!(index.sitemaps[0].loc.as_str(), "https://example.com/sitemap-pages.xml");
+        assert!(index.sitemaps[0].lastmod.is_some());
+        assert_eq!(index.sitemaps[1].lastmod, None);
+
+        assert!(matches!(SiteMapDocument::parse(xml)?, SiteMapDocument::Index(_)));
+        assert!(matches!(
+            SiteMapDocument::parse(include_str!("../test-site/sitemap.xml"))?,
+            SiteMapDocument::UrlSet(_)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_recursive() -> anyhow::Result<()> {
+        // Child <loc>s must be absolute, so the handler learns the server origin once it is bound
+        let origin: Arc<OnceLock<String>> = Arc::default();
+        let handler_origin = origin.clone();
+        let server = MockServer::start(move |request| {
+            let origin = handler_origin.get().unwrap();
+            let body = match request.path.as_str() {
+                // The root index lists two urlsets, a nested index, itself and a missing child
+                "/sitemap.xml" => format!(
+                    "<sitemapindex>
+                        <sitemap><loc>{origin}/a.xml</loc></sitemap>
+                        <sitemap><loc>{origin}/b.xml</loc></sitemap>
+                        <sitemap><loc>{origin}/nested.xml</loc></sitemap>
+                        <sitemap><loc>{origin}/sitemap.xml</loc></sitemap>
+                        <sitemap><loc>{origin}/missing.xml</loc></sitemap>
+                    </sitemapindex>"
+                ),
+                "/a.xml" => "<urlset><url><loc>https://example.com/1</loc></url><url><loc>https://example.com/2</loc></url></urlset>".to_string(),
+                "/b.xml" => "<urlset><url><loc>https://example.com/3</loc></url></urlset>".to_string(),
+                "/nested.xml" => format!("<sitemapindex><sitemap><loc>{origin}/c.xml</loc></sitemap></sitemapindex>"),
+                "/c.xml" => "<urlset><url><loc>https://example.com/4</loc></url></urlset>".to_string(),
+                _ => return MockResponse::new(404),
+            };
+            MockResponse::new(200).body(body)
+        })
+        .await?;
+        origin.set(server.url.as_str().trim_end_matches('/').to_string()).unwrap();
+
+        let client = Client::new();
+        let sitemap_url = server.url.join("/sitemap.xml")?;
+        let locs = |entries: Vec<SiteMapEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.loc.to_string()).collect()
+        };
+
+        // One level: the nested index is reached but not followed, the cycle is not refetched
+        let entries = SiteMap::fetch_recursive(&client, &sitemap_url, 1, None).await?;
+        assert_eq!(
+            locs(entries),
+            ["https://example.com/1", "https://example.com/2", "https://example.com/3"]
+        );
+
+        let entries = SiteMap::fetch_recursive(&client, &sitemap_url, 2, None).await?;
+        assert_eq!(locs(entries).len(), 4);
+
+        let entries = SiteMap::fetch_recursive(&client, &sitemap_url, 0, None).await?;
+        assert!(entries.is_empty());
+
+        let missing = server.url.join("/missing.xml")?;
+        assert!(SiteMap::fetch_recursive(&client, &missing, 1, None).await.is_err());
+
+        let fetched_root = server
+            .requests()
+            .iter()
+            .filter(|r| r.path == "/sitemap.xml")
+            .count();
+        assert_eq!(fetched_root, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gzipped_sitemap() -> anyhow::Result<()> {
+        const GZIPPED: &[u8] = include_bytes!("../test-site/sitemap.xml.gz");
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/sitemap.xml.gz" => MockResponse::new(200)
+                .header("Content-Type", "application/x-gzip")
+                .body(GZIPPED),
+            "/encoded.xml" => MockResponse::new(200)
+                .header("Content-Type", "application/xml")
+                .header("Content-Encoding", "gzip")
+                .body(GZIPPED),
+            "/plain.xml.gz" => MockResponse::new(200)
+                .header("Content-Type", "application/xml")
+                .body(include_str!("../test-site/sitemap.xml")),
+            "/not-xml.xml.gz" => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                std::io::Write::write_all(&mut encoder, b"just some text").unwrap();
+                MockResponse::new(200).body(encoder.finish().unwrap())
+            }
+            _ => MockResponse::new(404),
+        })
+        .await?;
+        let client = Client::new();
+
+        for path in ["/sitemap.xml.gz", "/encoded.xml", "/plain.xml.gz"] {
+            let xml = fetch_sitemap(&client, &server.url.join(path)?, None).await?;
+            assert_eq!(SiteMap::parse(&xml)?.urls.len(), 8, "{path}");
+        }
+
+        let error = fetch_sitemap(&client, &server.url.join("/not-xml.xml.gz")?, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("is not an XML document"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_discover_sitemaps() -> anyhow::Result<()> {
+        let client = Client::new();
+
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => MockResponse::new(200).body(
+                "User-agent: *\nDisallow: /private/\nSitemap: https://example.com/a.xml\nSitemap: https://example.com/b.xml.gz\n",
+            ),
+            _ => MockResponse::new(404),
+        })
+        .await?;
+        let discovery = discover_sitemaps(&client, &server.url.join("/deep/page")?, None, None).await?;
+        assert_eq!(
+            discovery.sitemaps,
+            ["https://example.com/a.xml", "https://example.com/b.xml.gz"]
+        );
+        assert!(discovery.fallback.is_none());
+
+        let fallback = MockServer::start(|request| match request.path.as_str() {
+            "/sitemap.xml" => MockResponse::new(200).body("<urlset/>"),
+            _ => MockResponse::new(404),
+        })
+        .await?;
+        let discovery = discover_sitemaps(&client, &fallback.url, None, None).await?;
+        let fallback_url = fallback.url.join("/sitemap.xml")?.to_string();
+        assert_eq!(discovery.sitemaps, vec![fallback_url.clone()]);
+        assert_eq!(discovery.fallback, Some((fallback_url, true)));
+
+        let empty = MockServer::start(|_| MockResponse::new(404)).await?;
+        let discovery = discover_sitemaps(&client, &empty.url, None, None).await?;
+        assert!(discovery.sitemaps.is_empty());
+        assert!(discovery.fallback.is_some_and(|(_, exists)| !exists));
+
+        Ok(())
+    }
+}
@@ -5,6 +5,20 @@ use std::thread;
 #[cfg(test)]
 use std::time::Duration;
 
+use url::Url;
+
+/// Returns the form of `url` used to recognise duplicates: no fragment and, optionally, no trailing slash
+/// Hosts are already lowercased and default ports dropped by the `url` parser
+pub fn normalize_url(url: &Url, strip_trailing_slash: bool) -> Url {
+    let mut normalized = url.clone();
+    normalized.set_fragment(None);
+    if strip_trailing_slash && normalized.path().len() > 1 && normalized.path().ends_with('/') {
+        let path = normalized.path().trim_end_matches('/').to_string();
+        normalized.set_path(if path.is_empty() { "/" } else { &path });
+    }
+    normalized
+}
+
 #[cfg(test)]
 pub struct PythonServer {
     port: i16,
@@ -40,3 +54,178 @@ impl Drop for PythonServer {
         }
     }
 }
+
+/// A canned HTTP response returned by [`MockServer`]
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[cfg(test)]
+impl MockResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// A request received by [`MockServer`]
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+impl MockRequest {
+    /// Returns the first value of a header, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Minimal HTTP/1.1 server for tests that need responses the Python file server can't produce
+/// (error statuses, redirects, custom headers). Every connection is answered once and closed.
+#[cfg(test)]
+pub struct MockServer {
+    pub url: url::Url,
+    requests: std::sync::Arc<std::sync::Mutex<Vec<MockRequest>>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(test)]
+impl MockServer {
+    pub async fn start<F>(handler: F) -> anyhow::Result<Self>
+    where
+        F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        use std::sync::{Arc, Mutex};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = url::Url::parse(&format!("http://{}/", listener.local_addr()?))?;
+        let requests: Arc<Mutex<Vec<MockRequest>>> = Arc::default();
+        let handler = Arc::new(handler);
+
+        let recorded = requests.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+
+                    let head = String::from_utf8_lossy(&buffer).to_string();
+                    let mut lines = head.split("\r\n");
+                    let request = MockRequest {
+                        path: lines
+                            .next()
+                            .and_then(|line| line.split_whitespace().nth(1))
+                            .unwrap_or_default()
+                            .to_string(),
+                        headers: lines
+                            .take_while(|line| !line.is_empty())
+                            .filter_map(|line| line.split_once(':'))
+                            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                            .collect(),
+                    };
+
+                    let response = handler(&request);
+                    recorded.lock().unwrap().push(request);
+
+                    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+                    for (name, value) in &response.headers {
+                        head.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    head.push_str(&format!(
+                        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        response.body.len()
+                    ));
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&response.body).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Ok(Self {
+            url,
+            requests,
+            handle,
+        })
+    }
+
+    /// Returns every request received so far, in arrival order
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use url::Url;
+
+    use super::normalize_url;
+
+    #[test]
+    fn test_normalize_url_strips_fragment() -> anyhow::Result<()> {
+        let url = Url::parse("http://example.com/a#section")?;
+        assert_eq!(normalize_url(&url, false).as_str(), "http://example.com/a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_url_drops_default_port_and_lowercases_host() -> anyhow::Result<()> {
+        let url = Url::parse("HTTP://Example.COM:80/a")?;
+        assert_eq!(normalize_url(&url, false).as_str(), "http://example.com/a");
+        let url = Url::parse("https://example.com:443/a")?;
+        assert_eq!(normalize_url(&url, false).as_str(), "https://example.com/a");
+        let url = Url::parse("https://example.com:8443/a")?;
+        assert_eq!(normalize_url(&url, false).as_str(), "https://example.com:8443/a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_url_trailing_slash() -> anyhow::Result<()> {
+        let url = Url::parse("http://example.com/a/?page=2")?;
+        assert_eq!(normalize_url(&url, false).as_str(), "http://example.com/a/?page=2");
+        assert_eq!(normalize_url(&url, true).as_str(), "http://example.com/a?page=2");
+        let root = Url::parse("http://example.com/")?;
+        assert_eq!(normalize_url(&root, true).as_str(), "http://example.com/");
+        Ok(())
+    }
+}